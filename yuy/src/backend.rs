@@ -0,0 +1,666 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use crate::ollama::OllamaClient;
+use crate::preset::Preset;
+use crate::server::LlamaServer;
+use crate::session::{Message, Role};
+use crate::utils::{command_exists, DEFAULT_CONTEXT};
+
+/// Where llama.cpp loads the model from.
+#[derive(Debug, Clone)]
+pub enum ModelSource {
+    File(PathBuf),
+    /// `org/repo:QUANT`, fetched by llama.cpp itself with `-hf`
+    HuggingFace(String),
+}
+
+/// How the runtime loads the model, from `--ctx`, `--threads`,
+/// `--gpu-layers` and arguments after `--`, or a `[models.<name>]` table
+/// in the config. Unset values keep the runtime's default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeOptions {
+    /// Context window in tokens
+    pub ctx_size: Option<u32>,
+    /// CPU threads used for generation
+    pub threads: Option<u32>,
+    /// Layers offloaded to the GPU (0 = CPU only)
+    pub gpu_layers: Option<u32>,
+    /// Passed to llama.cpp as is; `key=value` model options for Ollama
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+}
+
+impl RuntimeOptions {
+    /// These options with every one `overrides` sets replacing its own.
+    /// Extra arguments are appended, so later ones win in llama.cpp.
+    pub fn with_overrides(&self, overrides: &RuntimeOptions) -> RuntimeOptions {
+        RuntimeOptions {
+            ctx_size: overrides.ctx_size.or(self.ctx_size),
+            threads: overrides.threads.or(self.threads),
+            gpu_layers: overrides.gpu_layers.or(self.gpu_layers),
+            args: self.args.iter().chain(&overrides.args).cloned().collect(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.ctx_size == Some(0) {
+            anyhow::bail!("The context size must be > 0");
+        }
+        if self.threads == Some(0) {
+            anyhow::bail!("The thread count must be > 0");
+        }
+        Ok(())
+    }
+
+    /// `(name, value)` for every option that is set, for display.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        if let Some(v) = self.ctx_size {
+            params.push(("ctx_size", v.to_string()));
+        }
+        if let Some(v) = self.threads {
+            params.push(("threads", v.to_string()));
+        }
+        if let Some(v) = self.gpu_layers {
+            params.push(("gpu_layers", v.to_string()));
+        }
+        if !self.args.is_empty() {
+            params.push(("args", self.args.join(" ")));
+        }
+        params
+    }
+
+    /// llama.cpp flags for threads, GPU layers and the extra arguments.
+    /// The context size goes through the preset, which both llama-cli
+    /// and llama-server already read.
+    pub fn llama_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(threads) = self.threads {
+            args.extend(["-t".to_string(), threads.to_string()]);
+        }
+        if let Some(layers) = self.gpu_layers {
+            args.extend(["-ngl".to_string(), layers.to_string()]);
+        }
+        args.extend(self.args.iter().cloned());
+        args
+    }
+
+    /// Ollama model options (`num_thread`, `num_gpu`, plus every
+    /// `key=value` argument, with numbers and booleans sent as such).
+    pub fn ollama_options(&self) -> Result<serde_json::Map<String, serde_json::Value>> {
+        use serde_json::Value;
+        let mut options = serde_json::Map::new();
+        if let Some(threads) = self.threads {
+            options.insert("num_thread".to_string(), Value::from(threads));
+        }
+        if let Some(layers) = self.gpu_layers {
+            options.insert("num_gpu".to_string(), Value::from(layers));
+        }
+        for arg in &self.args {
+            let Some((key, value)) = arg.split_once('=').filter(|(k, _)| !k.is_empty()) else {
+                anyhow::bail!(
+                    "Ollama takes extra options as key=value (e.g. -- num_batch=512 use_mlock=true), not '{}'",
+                    arg
+                );
+            };
+            let value = serde_json::from_str(value).unwrap_or_else(|_| Value::from(value));
+            options.insert(key.to_string(), value);
+        }
+        Ok(options)
+    }
+}
+
+/// How a conversation is laid out in the prompt text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatFormat {
+    /// `<|im_start|>role ... <|im_end|>` (Qwen, Yuuki)
+    #[default]
+    Chatml,
+    /// `<|start_header_id|>role<|end_header_id|> ... <|eot_id|>`
+    Llama3,
+    /// `<start_of_turn>user ... <end_of_turn>`, no system role
+    Gemma,
+    /// `User: ...` / `Assistant: ...` transcript
+    Plain,
+}
+
+/// Marker llama.cpp prints when generation hits end-of-sequence.
+const END_OF_TEXT: &str = "[end of text]";
+
+impl ChatFormat {
+    /// Prompt ending with an open assistant turn.
+    pub fn render(&self, conversation: &[Message]) -> String {
+        match self {
+            ChatFormat::Chatml => {
+                let mut prompt = String::new();
+                for message in conversation {
+                    prompt.push_str(&format!(
+                        "<|im_start|>{}\n{}<|im_end|>\n",
+                        message.role.as_str(),
+                        message.content
+                    ));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+                prompt
+            }
+            ChatFormat::Llama3 => {
+                let mut prompt = String::new();
+                for message in conversation {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        message.role.as_str(),
+                        message.content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+                prompt
+            }
+            ChatFormat::Gemma => {
+                let mut prompt = String::new();
+                let mut system = None;
+                for message in conversation {
+                    let (role, content) = match message.role {
+                        Role::System => {
+                            system = Some(message.content.as_str());
+                            continue;
+                        }
+                        // Gemma has no system turn; it leads the next user turn
+                        Role::User => match system.take() {
+                            Some(system) => ("user", format!("{}\n\n{}", system, message.content)),
+                            None => ("user", message.content.clone()),
+                        },
+                        Role::Assistant => ("model", message.content.clone()),
+                    };
+                    prompt.push_str(&format!("<start_of_turn>{}\n{}<end_of_turn>\n", role, content));
+                }
+                prompt.push_str("<start_of_turn>model\n");
+                prompt
+            }
+            ChatFormat::Plain => transcript(conversation),
+        }
+    }
+
+    /// llama-server's `--chat-template` name. `Plain` keeps the model's own.
+    pub fn server_template(&self) -> Option<&'static str> {
+        match self {
+            ChatFormat::Chatml => Some("chatml"),
+            ChatFormat::Llama3 => Some("llama3"),
+            ChatFormat::Gemma => Some("gemma"),
+            ChatFormat::Plain => None,
+        }
+    }
+
+    /// Equivalent Ollama `TEMPLATE`. Ollama adds the `SYSTEM` prompt to
+    /// `.Messages`; Gemma has no system turn, so it becomes a user turn.
+    /// `Plain` keeps the model's own.
+    pub fn ollama_template(&self) -> Option<&'static str> {
+        match self {
+            ChatFormat::Chatml => Some(
+                "{{- range .Messages }}<|im_start|>{{ .Role }}\n{{ .Content }}<|im_end|>\n{{ end }}<|im_start|>assistant\n",
+            ),
+            ChatFormat::Llama3 => Some(
+                "{{- range .Messages }}<|start_header_id|>{{ .Role }}<|end_header_id|>\n\n{{ .Content }}<|eot_id|>{{ end }}<|start_header_id|>assistant<|end_header_id|>\n\n",
+            ),
+            ChatFormat::Gemma => Some(
+                "{{- range .Messages }}<start_of_turn>{{ if eq .Role \"assistant\" }}model{{ else }}user{{ end }}\n{{ .Content }}<end_of_turn>\n{{ end }}<start_of_turn>model\n",
+            ),
+            ChatFormat::Plain => None,
+        }
+    }
+
+    /// Special tokens that end a turn in this format.
+    pub fn stop_tokens(&self) -> &'static [&'static str] {
+        match self {
+            ChatFormat::Chatml => &["<|im_end|>"],
+            ChatFormat::Llama3 => &["<|eot_id|>"],
+            ChatFormat::Gemma => &["<end_of_turn>"],
+            ChatFormat::Plain => &[],
+        }
+    }
+
+    /// Text that ends a reply and is never shown.
+    fn stop_markers(&self) -> &'static [&'static str] {
+        match self {
+            ChatFormat::Chatml => &[END_OF_TEXT, "<|im_end|>"],
+            ChatFormat::Llama3 => &[END_OF_TEXT, "<|eot_id|>"],
+            ChatFormat::Gemma => &[END_OF_TEXT, "<end_of_turn>"],
+            ChatFormat::Plain => &[END_OF_TEXT, "\nUser:"],
+        }
+    }
+}
+
+/// A runtime process that exited unsuccessfully. `main` exits with the
+/// same status so scripts can tell runtime failures apart.
+#[derive(Debug)]
+pub struct RuntimeExit {
+    pub runtime: String,
+    pub code: Option<i32>,
+    /// Last line the runtime wrote to stderr
+    pub detail: String,
+}
+
+impl std::fmt::Display for RuntimeExit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "{} exited with status {}", self.runtime, code)?,
+            None => write!(f, "{} was terminated by a signal", self.runtime)?,
+        }
+        if !self.detail.is_empty() {
+            write!(f, ": {}", self.detail)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RuntimeExit {}
+
+/// Ctrl-C while a runtime was loading or replying. `main` exits with
+/// `INTERRUPTED_EXIT_CODE`.
+#[derive(Debug)]
+pub struct Interrupted;
+
+impl std::fmt::Display for Interrupted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Interrupted")
+    }
+}
+
+impl std::error::Error for Interrupted {}
+
+/// Exit status after Ctrl-C, as shells report for SIGINT.
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Exit status when no runtime can serve the model, as shells use for a
+/// command that isn't found.
+pub const NO_RUNTIME_EXIT_CODE: i32 = 127;
+
+/// A runtime that isn't installed or running, or doesn't have the model.
+/// The next runtime in the preference list is tried instead.
+#[derive(Debug)]
+pub struct RuntimeUnavailable {
+    pub runtime: String,
+    /// What is missing, with a hint to fix it
+    pub reason: String,
+}
+
+impl std::fmt::Display for RuntimeUnavailable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.reason)
+    }
+}
+
+impl std::error::Error for RuntimeUnavailable {}
+
+/// Every runtime tried was unavailable. `main` exits with
+/// `NO_RUNTIME_EXIT_CODE`.
+#[derive(Debug)]
+pub struct NoRuntime {
+    pub model: String,
+    pub tried: Vec<RuntimeUnavailable>,
+}
+
+impl std::fmt::Display for NoRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let [only] = self.tried.as_slice() {
+            return write!(f, "{}", only);
+        }
+        write!(f, "No runtime can run {}:", self.model)?;
+        for runtime in &self.tried {
+            write!(f, "\n  {}: {}", runtime.runtime, runtime.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NoRuntime {}
+
+/// A generated reply with the token counts the runtime reported.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Completion {
+    pub text: String,
+    pub prompt_tokens: Option<u64>,
+    pub completion_tokens: Option<u64>,
+}
+
+/// Generates replies to a conversation. Every turn sends the whole history,
+/// so Yuy owns the conversation state.
+#[derive(Debug, Clone)]
+pub enum Backend {
+    /// A fresh `llama-cli` process per turn
+    LlamaCpp { binary: String, model: ModelSource, format: ChatFormat, options: RuntimeOptions },
+    /// A `llama-server` kept running with the model loaded
+    LlamaServer(Arc<LlamaServer>),
+    /// Ollama's chat API; the server applies the model's chat template
+    Ollama { client: OllamaClient, model: String, options: RuntimeOptions },
+}
+
+impl Backend {
+    /// First llama.cpp CLI binary found on `PATH`.
+    pub fn find_llama_cli() -> Option<&'static str> {
+        ["llama-cli", "llama", "main"].into_iter().find(|cmd| command_exists(cmd))
+    }
+
+    /// Whether the runtime serves requests from one loaded model, so several
+    /// can run at once without loading it again.
+    pub fn is_server(&self) -> bool {
+        matches!(self, Backend::LlamaServer(_) | Backend::Ollama { .. })
+    }
+
+    /// Resolves once Ctrl-C has interrupted the runtime. Only Yuy's own
+    /// `llama-server` listens for it; other runtimes exit with Yuy.
+    pub async fn interrupted(&self) {
+        match self {
+            Backend::LlamaServer(server) => server.interrupted().await,
+            _ => std::future::pending().await,
+        }
+    }
+
+    /// Runs one turn, streaming the reply to `out` as it arrives, and
+    /// returns the full reply.
+    pub async fn complete(&self, conversation: &[Message], preset: &Preset, out: &mut dyn Write) -> Result<Completion> {
+        match self {
+            Backend::LlamaCpp { binary, model, format, options } => {
+                run_llama_cli(binary, model, *format, options, conversation, preset, out).await
+            }
+            Backend::LlamaServer(server) => server.chat(conversation, preset, out).await,
+            Backend::Ollama { client, model, options } => client.chat(model, conversation, preset, options, out).await,
+        }
+    }
+}
+
+/// One turn as a `llama-cli` process given the rendered conversation.
+async fn run_llama_cli(
+    binary: &str,
+    model: &ModelSource,
+    format: ChatFormat,
+    options: &RuntimeOptions,
+    conversation: &[Message],
+    preset: &Preset,
+    out: &mut dyn Write,
+) -> Result<Completion> {
+    let mut cmd = Command::new(binary);
+    match model {
+        ModelSource::File(path) => cmd.arg("-m").arg(path),
+        ModelSource::HuggingFace(hf) => cmd.arg("-hf").arg(hf),
+    };
+    let mut child = cmd
+        .arg("-p")
+        .arg(format.render(conversation))
+        .arg("-no-cnv")
+        .arg("--no-display-prompt")
+        .args(llama_args(preset))
+        .args(options.llama_args())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("Failed to start {}", binary))?;
+
+    // Drain stderr alongside so a chatty runtime can't block on a full pipe
+    let mut stderr = child.stderr.take().expect("stderr is piped");
+    let stderr_reader = tokio::spawn(async move {
+        let mut buf = String::new();
+        let _ = stderr.read_to_string(&mut buf).await;
+        buf
+    });
+
+    let markers = format.stop_markers();
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut reply = Vec::new();
+    let mut shown = 0;
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = stdout.read(&mut buf).await?;
+        reply.extend_from_slice(&buf[..n]);
+        let end = visible_end(&reply, markers, n == 0).max(shown);
+        out.write_all(&reply[shown..end])?;
+        out.flush()?;
+        shown = end;
+        if n == 0 {
+            break;
+        }
+    }
+
+    let status = child.wait().await?;
+    let stderr = stderr_reader.await.unwrap_or_default();
+    if !status.success() {
+        let detail = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("").trim();
+        return Err(RuntimeExit {
+            runtime: binary.to_string(),
+            code: status.code(),
+            detail: detail.to_string(),
+        }
+        .into());
+    }
+
+    let (prompt_tokens, completion_tokens) = token_counts(&stderr);
+    Ok(Completion {
+        text: clean_reply(&String::from_utf8_lossy(&reply), markers),
+        prompt_tokens,
+        completion_tokens,
+    })
+}
+
+/// llama.cpp flags for the parameters a preset sets.
+fn llama_args(preset: &Preset) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut push = |flag: &str, value: Option<String>| {
+        if let Some(value) = value {
+            args.push(flag.to_string());
+            args.push(value);
+        }
+    };
+    push("--temp", preset.temperature.map(|v| v.to_string()));
+    push("--top-p", preset.top_p.map(|v| v.to_string()));
+    push("--top-k", preset.top_k.map(|v| v.to_string()));
+    push("--min-p", preset.min_p.map(|v| v.to_string()));
+    push("--repeat-penalty", preset.repeat_penalty.map(|v| v.to_string()));
+    push("--seed", preset.seed.map(|v| v.to_string()));
+    push("-c", Some(preset.ctx_size.unwrap_or(DEFAULT_CONTEXT).to_string()));
+    push("-n", preset.n_predict.map(|v| v.to_string()));
+    push("--mirostat", preset.mirostat.map(|v| v.to_string()));
+    push("--mirostat-ent", preset.mirostat_tau.map(|v| v.to_string()));
+    push("--mirostat-lr", preset.mirostat_eta.map(|v| v.to_string()));
+    args
+}
+
+/// Plain-text history for runtimes that apply their own chat template to a
+/// single prompt. A lone user message is passed through unchanged.
+fn transcript(conversation: &[Message]) -> String {
+    if let [only] = conversation {
+        if only.role == Role::User {
+            return only.content.clone();
+        }
+    }
+    let mut text = String::new();
+    for message in conversation {
+        let speaker = match message.role {
+            Role::System => "System",
+            Role::User => "User",
+            Role::Assistant => "Assistant",
+        };
+        text.push_str(&format!("{}: {}\n\n", speaker, message.content.trim_end()));
+    }
+    text.push_str("Assistant:");
+    text
+}
+
+/// How much of `reply` can be shown: everything before a stop marker, minus
+/// a tail that might be the start of one unless the stream is `done`.
+fn visible_end(reply: &[u8], markers: &[&str], done: bool) -> usize {
+    let stop = markers
+        .iter()
+        .filter_map(|m| reply.windows(m.len()).position(|w| w == m.as_bytes()))
+        .min();
+    if let Some(stop) = stop {
+        return stop;
+    }
+    if done {
+        return reply.len();
+    }
+    let longest = markers.iter().map(|m| m.len()).max().unwrap_or(0);
+    reply.len().saturating_sub(longest - 1)
+}
+
+/// `(prompt, completion)` token counts from llama.cpp's stderr statistics:
+///
+/// ```text
+/// llama_perf_context_print: prompt eval time =  51.20 ms /  12 tokens (...)
+/// llama_perf_context_print:        eval time = 802.11 ms /  85 runs   (...)
+/// ```
+fn token_counts(stderr: &str) -> (Option<u64>, Option<u64>) {
+    let mut prompt = None;
+    let mut completion = None;
+    for line in stderr.lines() {
+        let stat = line.rsplit_once("print:").map_or(line, |(_, s)| s).trim();
+        let Some((_, rest)) = stat.split_once("eval time") else {
+            continue;
+        };
+        // "= 51.20 ms / 12 tokens"
+        let count = rest
+            .rsplit_once('/')
+            .and_then(|(_, n)| n.split_whitespace().next()?.parse().ok());
+        if stat.starts_with("prompt eval") {
+            prompt = prompt.or(count);
+        } else if stat.starts_with("eval") {
+            completion = completion.or(count);
+        }
+    }
+    (prompt, completion)
+}
+
+fn clean_reply(raw: &str, markers: &[&str]) -> String {
+    let end = visible_end(raw.as_bytes(), markers, true);
+    String::from_utf8_lossy(&raw.as_bytes()[..end]).trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+
+    #[test]
+    fn renders_chat_formats() {
+        let conversation = [
+            Message::new(Role::System, "Be brief."),
+            Message::new(Role::User, "hi"),
+        ];
+        assert_eq!(
+            ChatFormat::Chatml.render(&conversation),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nhi<|im_end|>\n<|im_start|>assistant\n"
+        );
+        assert_eq!(
+            ChatFormat::Llama3.render(&conversation),
+            "<|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nhi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+        assert_eq!(
+            ChatFormat::Gemma.render(&conversation),
+            "<start_of_turn>user\nBe brief.\n\nhi<end_of_turn>\n<start_of_turn>model\n"
+        );
+    }
+
+    #[test]
+    fn transcript_passes_single_message_through() {
+        assert_eq!(transcript(&[Message::new(Role::User, "hi")]), "hi");
+        let conversation = [
+            Message::new(Role::User, "hi"),
+            Message::new(Role::Assistant, "hello"),
+            Message::new(Role::User, "bye"),
+        ];
+        assert_eq!(transcript(&conversation), "User: hi\n\nAssistant: hello\n\nUser: bye\n\nAssistant:");
+    }
+
+    #[test]
+    fn maps_preset_to_llama_flags() {
+        let preset = Preset {
+            temperature: Some(0.7),
+            top_k: Some(40),
+            seed: Some(42),
+            n_predict: Some(-1),
+            mirostat: Some(2),
+            mirostat_tau: Some(5.0),
+            ..Preset::default()
+        };
+        assert_eq!(
+            llama_args(&preset).join(" "),
+            "--temp 0.7 --top-k 40 --seed 42 -c 4096 -n -1 --mirostat 2 --mirostat-ent 5"
+        );
+
+        let long = Preset { ctx_size: Some(16384), ..Preset::default() };
+        assert_eq!(llama_args(&long), ["-c", "16384"]);
+    }
+
+    #[test]
+    fn merges_runtime_options_over_model_defaults() {
+        let defaults: RuntimeOptions = toml::from_str("threads = 4\ngpu_layers = 99\nargs = [\"--mlock\"]").unwrap();
+        let flags = RuntimeOptions {
+            gpu_layers: Some(0),
+            args: vec!["--grammar-file".to_string(), "json.gbnf".to_string()],
+            ..RuntimeOptions::default()
+        };
+        let options = defaults.with_overrides(&flags);
+        assert_eq!(options.llama_args().join(" "), "-t 4 -ngl 0 --mlock --grammar-file json.gbnf");
+        assert!(RuntimeOptions { threads: Some(0), ..RuntimeOptions::default() }.validate().is_err());
+        assert!(toml::from_str::<RuntimeOptions>("gpu_layer = 1").is_err());
+    }
+
+    #[test]
+    fn parses_token_counts_from_runtime_stats() {
+        let llama = "\
+llama_perf_sampler_print:    sampling time =       2.10 ms /    97 runs   (    0.02 ms per token)
+llama_perf_context_print:        load time =     310.55 ms
+llama_perf_context_print: prompt eval time =      51.20 ms /    12 tokens (    4.27 ms per token)
+llama_perf_context_print:        eval time =     802.11 ms /    85 runs   (    9.44 ms per token)
+";
+        assert_eq!(token_counts(llama), (Some(12), Some(85)));
+        assert_eq!(token_counts("loading model\n"), (None, None));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn streams_reply_from_llama_cli() {
+        // Stand-in for llama-cli: answers ChatML prompts, exits 3 when asked to fail
+        let dir = tempfile::tempdir().unwrap();
+        let script = fake_binary(
+            dir.path(),
+            "llama-cli",
+            "echo 'loading model' >&2\ncase \"$*\" in *fail*) exit 3 ;; *'<|im_start|>assistant'*) printf ' Hello there! [end of text]\\n' ;; esac\necho 'llama_perf_context_print:        eval time = 802.11 ms /    85 runs' >&2\n",
+        );
+
+        let backend = Backend::LlamaCpp {
+            binary: script.to_string_lossy().to_string(),
+            model: ModelSource::File(PathBuf::from("model.gguf")),
+            format: ChatFormat::Chatml,
+            options: RuntimeOptions::default(),
+        };
+        let mut out = Vec::new();
+        let reply = backend
+            .complete(&[Message::new(Role::User, "hi")], &Preset::default(), &mut out)
+            .await
+            .unwrap();
+
+        assert_eq!(reply.text, "Hello there!");
+        assert_eq!(reply.completion_tokens, Some(85));
+        assert_eq!(String::from_utf8(out).unwrap(), " Hello there! ");
+
+        let err = backend
+            .complete(&[Message::new(Role::User, "fail")], &Preset::default(), &mut Vec::new())
+            .await
+            .unwrap_err();
+        let exit = err.downcast_ref::<RuntimeExit>().unwrap();
+        assert_eq!(exit.code, Some(3));
+        assert_eq!(exit.detail, "loading model");
+    }
+}
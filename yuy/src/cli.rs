@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser)]
 #[command(name = "yuy")]
@@ -6,6 +7,14 @@ use clap::{Parser, Subcommand};
 #[command(version = "0.1.0")]
 #[command(arg_required_else_help = false)]
 pub struct Cli {
+    /// Keep every file under DIR instead of ~/.yuuki (also: YUUKI_HOME)
+    #[arg(long, global = true, value_name = "DIR")]
+    pub home: Option<PathBuf>,
+
+    /// Apply [profiles.NAME] from the config (also: YUY_PROFILE)
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+
     #[command(subcommand)]
     pub command: Option<Commands>,
 }
@@ -14,20 +23,28 @@ pub struct Cli {
 pub enum Commands {
     /// Download a model from Hugging Face
     Download {
-        /// Model name (Yuuki-best, Yuuki-3.7, Yuuki-v0.1)
-        model: String,
+        /// Model name (Yuuki-best, Yuuki-3.7, Yuuki-v0.1); defaults to default_model
+        model: Option<String>,
 
         /// Specific quantization to download (q4_0, q5_k_m, q8_0, f32)
         #[arg(short, long)]
         quant: Option<String>,
+
+        /// Number of parallel connections (defaults to download_connections in config)
+        #[arg(long)]
+        connections: Option<usize>,
+
+        /// Print the effective settings and where they came from, then exit
+        #[arg(long)]
+        explain: bool,
     },
 
     /// Run a model with Yuuki Runtime
     Run {
-        /// Model name
-        model: String,
+        /// Model name; defaults to default_model
+        model: Option<String>,
 
-        /// Runtime to use (llama-cpp or ollama)
+        /// Runtime to use (llama-cpp, llama-hf or ollama); default: first available
         #[arg(short, long)]
         runtime: Option<String>,
 
@@ -35,17 +52,88 @@ pub enum Commands {
         #[arg(short, long)]
         quant: Option<String>,
 
-        /// Preset configuration (creative, precise, balanced)
-        #[arg(short, long)]
+        /// Generation preset (see `yuy preset list`)
+        #[arg(long)]
         preset: Option<String>,
 
-        /// Resume last conversation
+        /// Resume the last conversation (or the one named by --session)
         #[arg(long)]
         resume: bool,
 
-        /// Use a specific template
+        /// Name of the session to continue or create
+        #[arg(short, long)]
+        session: Option<String>,
+
+        /// Prompt template to use (see `yuy template list`)
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Template variable, e.g. --var language=Spanish (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Answer a single prompt and exit, printing only the reply
+        #[arg(short, long)]
+        prompt: Option<String>,
+
+        /// Context window in tokens
+        #[arg(long, value_name = "TOKENS")]
+        ctx: Option<u32>,
+
+        /// CPU threads used for generation
+        #[arg(long)]
+        threads: Option<u32>,
+
+        /// Layers to offload to the GPU (0 = CPU only)
+        #[arg(long, value_name = "N")]
+        gpu_layers: Option<u32>,
+
+        /// Extra runtime arguments after `--`, e.g. -- --mlock -b 512
+        /// (Ollama: key=value options, e.g. -- num_batch=512)
+        #[arg(last = true, value_name = "ARGS")]
+        args: Vec<String>,
+
+        /// Print the effective settings and where they came from, then exit
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Run every prompt in a JSONL file and write the replies as JSONL
+    Batch {
+        /// Model name; defaults to default_model
+        model: Option<String>,
+
+        /// Prompts, one JSON object per line: {"id": ..., "prompt": ..., "system": ...}
+        #[arg(short, long)]
+        input: PathBuf,
+
+        /// Results file; prompts already answered in it are skipped
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Runtime to use (llama-cpp, llama-hf or ollama); default: first available
+        #[arg(short, long)]
+        runtime: Option<String>,
+
+        /// Quantization to use
+        #[arg(short, long)]
+        quant: Option<String>,
+
+        /// Generation preset; lines can override single parameters
+        #[arg(long)]
+        preset: Option<String>,
+
+        /// Prompt template for lines without their own system prompt
         #[arg(short, long)]
         template: Option<String>,
+
+        /// Template variable, e.g. --var language=Spanish (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Prompts to run at once (server runtimes only)
+        #[arg(short = 'j', long, default_value_t = 1)]
+        concurrency: usize,
     },
 
     /// List models or other resources
@@ -70,15 +158,88 @@ pub enum Commands {
         model: String,
     },
 
+    /// Re-hash downloaded files and compare them with Hugging Face checksums
+    Verify {
+        /// Model name to verify
+        model: String,
+    },
+
+    /// Manage saved conversations
+    Sessions {
+        #[command(subcommand)]
+        action: SessionsAction,
+    },
+
+    /// Manage where downloaded models are stored
+    Models {
+        #[command(subcommand)]
+        action: ModelsAction,
+    },
+
+    /// Manage prompt templates
+    Template {
+        #[command(subcommand)]
+        action: TemplateAction,
+    },
+
+    /// Manage generation presets
+    Preset {
+        #[command(subcommand)]
+        action: PresetAction,
+    },
+
     /// Manage runtimes (llama.cpp, ollama)
     Runtime {
         #[command(subcommand)]
         action: RuntimeAction,
     },
 
+    /// Use downloaded models with Ollama
+    Ollama {
+        #[command(subcommand)]
+        action: OllamaAction,
+    },
+
+    /// Manage the model registry
+    Registry {
+        #[command(subcommand)]
+        action: RegistryAction,
+    },
+
+    /// Read and change settings in ~/.yuuki/config.toml
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Save a Hugging Face token for gated and private repos
+    Login {
+        /// Access token (prompted for when omitted)
+        #[arg(long)]
+        token: Option<String>,
+    },
+
+    /// Remove the saved Hugging Face token
+    Logout,
+
     /// Check system health and show diagnostics
     Doctor,
 
+    /// Move config, models and sessions to a new home or the XDG layout
+    MigrateHome {
+        /// New home directory, holding everything as ~/.yuuki does
+        #[arg(long, value_name = "DIR", required_unless_present = "xdg", conflicts_with = "xdg")]
+        to: Option<PathBuf>,
+
+        /// Split into $XDG_CONFIG_HOME, $XDG_DATA_HOME and $XDG_CACHE_HOME
+        #[arg(long)]
+        xdg: bool,
+
+        /// Print what would move without moving anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
     /// Initial setup wizard
     Setup,
 }
@@ -93,6 +254,195 @@ pub enum ListTarget {
     },
 }
 
+#[derive(Subcommand)]
+pub enum ModelsAction {
+    /// Move a downloaded model to another storage root
+    Move {
+        /// Model name
+        model: String,
+
+        /// Storage root to move it to (one of model_roots, or the default models dir)
+        #[arg(long, value_name = "ROOT")]
+        to: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum SessionsAction {
+    /// List saved sessions, most recent first
+    List,
+
+    /// Print a session's settings and messages
+    Show {
+        /// Session name
+        name: String,
+    },
+
+    /// Delete a session
+    Delete {
+        /// Session name
+        name: String,
+    },
+
+    /// Export a session as Markdown or JSON
+    Export {
+        /// Session name
+        name: String,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Markdown)]
+        format: ExportFormat,
+
+        /// Write to a file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum TemplateAction {
+    /// List built-in and user templates
+    List,
+
+    /// Print a template's variables and system prompt
+    Show {
+        /// Template name
+        name: String,
+    },
+
+    /// Create a template in ~/.yuuki/templates
+    New {
+        /// Template name
+        name: String,
+
+        /// Start from an existing template
+        #[arg(long)]
+        from: Option<String>,
+    },
+
+    /// Open a template in $EDITOR (built-ins are copied first)
+    Edit {
+        /// Template name
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum PresetAction {
+    /// List built-in, config and user presets
+    List,
+
+    /// Print a preset's parameters
+    Show {
+        /// Preset name
+        name: String,
+    },
+
+    /// Create a preset in ~/.yuuki/presets
+    New {
+        /// Preset name
+        name: String,
+
+        /// Start from an existing preset (defaults to balanced)
+        #[arg(long)]
+        from: Option<String>,
+    },
+
+    /// Open a preset in $EDITOR (others are copied first)
+    Edit {
+        /// Preset name
+        name: String,
+    },
+
+    /// Delete a preset file
+    Delete {
+        /// Preset name
+        name: String,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    Markdown,
+    Json,
+}
+
+#[derive(Subcommand)]
+pub enum RegistryAction {
+    /// Fetch the latest registry from registry_url
+    Update,
+
+    /// Show registry sources and known models
+    Show,
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Print a value
+    Get {
+        /// Key, e.g. default_quant or presets.long.temperature
+        key: String,
+    },
+
+    /// Check a value and save it
+    Set {
+        /// Key, e.g. default_quant or models.Yuuki-best.threads
+        key: String,
+
+        /// Value; lists such as runtimes are comma-separated
+        #[arg(allow_hyphen_values = true)]
+        value: String,
+    },
+
+    /// Remove a key
+    Unset {
+        /// Key to remove
+        key: String,
+    },
+
+    /// Print every setting in the file and check them
+    List,
+
+    /// Open the config in $EDITOR and check it afterwards
+    Edit,
+
+    /// Print where the config file lives
+    Path,
+}
+
+#[derive(Subcommand)]
+pub enum OllamaAction {
+    /// Register a downloaded GGUF with Ollama so it needn't be pulled again
+    Import {
+        /// Model name
+        model: String,
+
+        /// Quantization to import (required when several are downloaded)
+        #[arg(short, long)]
+        quant: Option<String>,
+
+        /// Generation preset to bake in as parameters
+        #[arg(long)]
+        preset: Option<String>,
+
+        /// Prompt template for the system prompt and chat format
+        #[arg(short, long)]
+        template: Option<String>,
+
+        /// Template variable, e.g. --var language=Spanish (repeatable)
+        #[arg(long = "var", value_name = "KEY=VALUE")]
+        vars: Vec<String>,
+
+        /// Ollama model name (default: the one `yuy run --runtime ollama` uses)
+        #[arg(long)]
+        name: Option<String>,
+
+        /// Only write the Modelfile, without registering it
+        #[arg(long)]
+        modelfile_only: bool,
+    },
+}
+
 #[derive(Subcommand)]
 pub enum RuntimeAction {
     /// Check installed runtimes
@@ -0,0 +1,383 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use futures_util::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use crate::backend::{Backend, ChatFormat, Interrupted, RuntimeOptions};
+use crate::commands::run::{auto_quant, pick_backend, render_template, runtime_options};
+use crate::preset::{Preset, PresetStore, SAMPLING_FIELDS};
+use crate::registry::Registry;
+use crate::session::{Message, Role};
+use crate::settings::Settings;
+use crate::utils::did_you_mean;
+
+/// Flags of `yuy batch`.
+#[derive(Debug, Default)]
+pub struct BatchOptions {
+    pub input: PathBuf,
+    pub output: PathBuf,
+    pub runtime: Option<String>,
+    pub quant: Option<String>,
+    pub preset: Option<String>,
+    pub template: Option<String>,
+    pub vars: Vec<String>,
+    pub concurrency: usize,
+}
+
+/// One line of the input file:
+///
+/// ```json
+/// {"id": "q1", "prompt": "...", "system": "...", "temperature": 0.2}
+/// ```
+///
+/// `id` defaults to the line number; any sampling parameter, and
+/// `n_predict`, may be given to override `--preset` for that prompt.
+#[derive(Debug, Deserialize)]
+struct BatchItem {
+    #[serde(default)]
+    id: Option<Value>,
+    prompt: String,
+    #[serde(default)]
+    system: Option<String>,
+    /// Everything else, checked against `item_parameters`
+    #[serde(flatten)]
+    extra: serde_json::Map<String, Value>,
+    #[serde(skip)]
+    params: Preset,
+}
+
+/// Preset fields a line can set. The rest (`name`, `ctx_size`, ...) only
+/// make sense for the whole batch.
+fn item_parameters() -> impl Iterator<Item = &'static str> {
+    SAMPLING_FIELDS.iter().copied().chain(["n_predict"])
+}
+
+/// One line of the output file. `error` is set instead of `output` when the
+/// prompt failed; failed prompts are retried on the next run.
+#[derive(Debug, Serialize)]
+struct BatchResult {
+    id: Value,
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    output: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    elapsed_ms: u64,
+    prompt_tokens: Option<u64>,
+    completion_tokens: Option<u64>,
+}
+
+pub async fn execute(model: Option<String>, options: BatchOptions) -> Result<()> {
+    let BatchOptions { input, output, runtime, quant, preset, template, vars, concurrency } = options;
+
+    println!("{}", "📦 Yuuki Batch".bright_cyan().bold());
+    println!();
+
+    let items = read_items(&input)?;
+    let done = completed_ids(&output)?;
+    let total = items.len();
+    let pending: Vec<BatchItem> = items
+        .into_iter()
+        .filter(|item| !done.contains(&item_key(item)))
+        .collect();
+
+    let settings = Settings::load()?;
+    let model_ref = Registry::load()?.resolve(&settings.model(model).value)?;
+    let entry = &model_ref.entry;
+    let quant = match settings.quant(model_ref.quant_setting(quant)?) {
+        Some(quant) => quant.value,
+        None => auto_quant(entry)?.value,
+    };
+    let chain = settings.runtimes(runtime, None)?.value;
+    let mut sampling = PresetStore::open()?.find(preset.as_deref())?;
+    let load = runtime_options(&entry.name, &RuntimeOptions::default(), &mut sampling)?;
+    let (system, format) = match template {
+        Some(name) => {
+            let (template, system) = render_template(&name, &vars)?;
+            (Some(system), template.format)
+        }
+        None if !vars.is_empty() => {
+            anyhow::bail!("--var needs a template. Pick one with --template (see: yuy template list)")
+        }
+        None => (None, ChatFormat::default()),
+    };
+
+    // Catch bad per-line parameters before spending time on the others
+    for item in &pending {
+        sampling
+            .with_overrides(&item.params)
+            .validate()
+            .with_context(|| format!("Invalid parameters for item {}", item_key(item)))?;
+    }
+
+    let (runtime, backend) = pick_backend(entry, &chain, &quant, format, &sampling, &load, concurrency).await?;
+
+    let workers = if concurrency > 1 && !backend.is_server() {
+        eprintln!(
+            "{} {}",
+            "⚠".bright_yellow(),
+            format!(
+                "{} loads the model once per prompt; running one at a time (--concurrency needs llama-server or ollama)",
+                runtime
+            )
+            .bright_yellow()
+        );
+        1
+    } else {
+        concurrency.clamp(1, pending.len().max(1))
+    };
+    println!("{} Model: {} ({}, {})", "→".bright_blue(), entry.name.bright_green(), quant, runtime);
+    println!("{} Input: {} ({} prompts)", "→".bright_blue(), input.display().to_string().bright_white(), total);
+    println!("{} Output: {}", "→".bright_blue(), output.display().to_string().bright_white());
+    if !done.is_empty() {
+        println!(
+            "{} Skipping {} prompts already in the output",
+            "ℹ".bright_blue(),
+            total - pending.len()
+        );
+    }
+    if pending.is_empty() {
+        println!("{} Nothing to do", "✓".bright_green());
+        return Ok(());
+    }
+    if workers > 1 {
+        println!("{} Concurrency: {}", "→".bright_blue(), workers);
+    }
+    println!();
+
+    let mut file = open_output(&output)?;
+    let pb = ProgressBar::new(pending.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({eta})")?
+            .progress_chars("#>-"),
+    );
+
+    let started = Instant::now();
+    let count = pending.len();
+    let mut failed = 0;
+    let mut generated = 0;
+
+    // Up to `workers` prompts in flight, written in the order they finish
+    let mut results = futures_util::stream::iter(pending)
+        .map(|item| run_item(&backend, &sampling, system.as_deref(), item, &entry.name))
+        .buffer_unordered(workers);
+    loop {
+        let result = tokio::select! {
+            // Prompts cut short by Ctrl-C aren't recorded as failures
+            biased;
+            _ = backend.interrupted() => {
+                pb.finish_and_clear();
+                return Err(Interrupted.into());
+            }
+            result = results.next() => result,
+        };
+        let Some(result) = result else {
+            break;
+        };
+        if let Some(error) = &result.error {
+            failed += 1;
+            pb.suspend(|| eprintln!("{} {}: {}", "✗".bright_red(), result.id, error));
+        }
+        generated += result.completion_tokens.unwrap_or(0);
+        // One flushed line per prompt so an interrupted batch can resume
+        serde_json::to_writer(&mut file, &result)?;
+        file.write_all(b"\n")?;
+        file.flush()?;
+        pb.inc(1);
+    }
+    pb.finish_and_clear();
+
+    let elapsed = started.elapsed().as_secs_f64();
+    println!(
+        "{} {} of {} prompts done in {:.1}s",
+        "✓".bright_green(),
+        count - failed,
+        count,
+        elapsed
+    );
+    if generated > 0 {
+        println!(
+            "{} {} tokens generated ({:.1} tokens/s)",
+            "→".bright_blue(),
+            generated,
+            generated as f64 / elapsed
+        );
+    }
+    if failed > 0 {
+        anyhow::bail!("{} prompts failed. Run the same command again to retry them.", failed);
+    }
+    Ok(())
+}
+
+async fn run_item(backend: &Backend, sampling: &Preset, system: Option<&str>, item: BatchItem, model: &str) -> BatchResult {
+    let mut conversation = Vec::new();
+    if let Some(system) = item.system.as_deref().or(system) {
+        conversation.push(Message::new(Role::System, system));
+    }
+    conversation.push(Message::new(Role::User, item.prompt.as_str()));
+
+    let started = Instant::now();
+    let reply = backend.complete(&conversation, &sampling.with_overrides(&item.params), &mut std::io::sink()).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    let id = item.id.unwrap_or(Value::Null);
+    let model = model.to_string();
+    match reply {
+        Ok(reply) => BatchResult {
+            id,
+            model,
+            output: Some(reply.text),
+            error: None,
+            elapsed_ms,
+            prompt_tokens: reply.prompt_tokens,
+            completion_tokens: reply.completion_tokens,
+        },
+        Err(e) => BatchResult {
+            id,
+            model,
+            output: None,
+            error: Some(e.to_string()),
+            elapsed_ms,
+            prompt_tokens: None,
+            completion_tokens: None,
+        },
+    }
+}
+
+/// Parses the input file, giving items without an `id` their line number.
+fn read_items(path: &Path) -> Result<Vec<BatchItem>> {
+    let content = fs::read_to_string(path).with_context(|| format!("Could not read {}", path.display()))?;
+    let mut items = Vec::new();
+    let mut seen = HashSet::new();
+    for (index, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid = |e: &dyn std::fmt::Display| {
+            anyhow::anyhow!("Invalid prompt on line {} of {}: {}", index + 1, path.display(), e)
+        };
+        let mut item: BatchItem = serde_json::from_str(line).map_err(|e| invalid(&e))?;
+        if let Some(key) = item.extra.keys().find(|k| !item_parameters().any(|p| p == k.as_str())) {
+            let hint = match did_you_mean(key, item_parameters()) {
+                Some(known) => format!("Did you mean '{}'?", known),
+                None => format!("Lines take id, prompt, system, {}.", item_parameters().collect::<Vec<_>>().join(", ")),
+            };
+            return Err(invalid(&format!("unknown field '{}'. {}", key, hint)));
+        }
+        item.params = serde_json::from_value(Value::Object(std::mem::take(&mut item.extra))).map_err(|e| invalid(&e))?;
+        if item.id.is_none() {
+            item.id = Some(Value::from(index + 1));
+        }
+        if !seen.insert(item_key(&item)) {
+            anyhow::bail!("Duplicate id {} on line {} of {}", item_key(&item), index + 1, path.display());
+        }
+        items.push(item);
+    }
+    if items.is_empty() {
+        anyhow::bail!("No prompts in {}", path.display());
+    }
+    Ok(items)
+}
+
+/// Ids with a successful result in an existing output file. A partly
+/// written last line from an interrupted run is ignored.
+fn completed_ids(path: &Path) -> Result<HashSet<String>> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(HashSet::new()),
+        Err(e) => return Err(e).with_context(|| format!("Could not read {}", path.display())),
+    };
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter(|result| result.get("error").is_none_or(Value::is_null))
+        .filter_map(|result| result.get("id").map(Value::to_string))
+        .collect())
+}
+
+/// Opens the output for appending, finishing a partly written last line.
+fn open_output(path: &Path) -> Result<fs::File> {
+    let unterminated = fs::read(path).is_ok_and(|bytes| bytes.last().is_some_and(|&b| b != b'\n'));
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Could not open {}", path.display()))?;
+    if unterminated {
+        file.write_all(b"\n")?;
+    }
+    Ok(file)
+}
+
+/// Key used to match items against earlier results: the id as JSON, so
+/// `1` and `"1"` stay distinct.
+fn item_key(item: &BatchItem) -> String {
+    item.id.as_ref().map(Value::to_string).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_items_with_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("prompts.jsonl");
+        fs::write(
+            &input,
+            "{\"id\": \"a\", \"prompt\": \"Hi\", \"system\": \"Be brief.\", \"temperature\": 0.1, \"n_predict\": 32}\n\n{\"prompt\": \"Second\"}\n",
+        )
+        .unwrap();
+
+        let items = read_items(&input).unwrap();
+        assert_eq!(items.len(), 2);
+        assert_eq!(item_key(&items[0]), "\"a\"");
+        assert_eq!(items[0].system.as_deref(), Some("Be brief."));
+        assert_eq!(items[0].params.temperature, Some(0.1));
+        assert_eq!(items[0].params.n_predict, Some(32));
+        // Blank lines still count towards the line number
+        assert_eq!(items[1].id, Some(Value::from(3)));
+
+        let base = Preset { temperature: Some(0.6), top_p: Some(0.7), ..Preset::default() };
+        let merged = base.with_overrides(&items[0].params);
+        assert_eq!((merged.temperature, merged.top_p), (Some(0.1), Some(0.7)));
+
+        fs::write(&input, "{\"prompt\": \"Hi\", \"temprature\": 0.1}\n").unwrap();
+        let err = read_items(&input).unwrap_err().to_string();
+        assert!(err.contains("Did you mean 'temperature'?"), "{}", err);
+        for field in ["name", "description", "ctx_size"] {
+            fs::write(&input, format!("{{\"prompt\": \"Hi\", \"{}\": \"x\"}}\n", field)).unwrap();
+            let err = read_items(&input).unwrap_err().to_string();
+            assert!(err.contains(&format!("unknown field '{}'", field)), "{}", err);
+        }
+        fs::write(&input, "{\"id\": 1, \"prompt\": \"a\"}\n{\"id\": 1, \"prompt\": \"b\"}\n").unwrap();
+        assert!(read_items(&input).unwrap_err().to_string().contains("Duplicate id 1"));
+    }
+
+    #[test]
+    fn resumes_from_successful_results() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("results.jsonl");
+        assert!(completed_ids(&output).unwrap().is_empty());
+
+        fs::write(
+            &output,
+            "{\"id\":\"a\",\"output\":\"ok\"}\n{\"id\":2,\"error\":\"boom\"}\n{\"id\":3,\"output\":\"ok\"}\n{\"id\":\"b\",\"out",
+        )
+        .unwrap();
+        let done = completed_ids(&output).unwrap();
+        assert_eq!(done, HashSet::from(["\"a\"".to_string(), "3".to_string()]));
+
+        let mut file = open_output(&output).unwrap();
+        file.write_all(b"{\"id\":\"b\"}\n").unwrap();
+        let content = fs::read_to_string(&output).unwrap();
+        assert!(content.ends_with("\"out\n{\"id\":\"b\"}\n"));
+    }
+}
@@ -0,0 +1,418 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use crate::cli::ConfigAction;
+use crate::config::{
+    active_profile, get_config_path, overlay, parse_config_file, save_config, table_get, Config, ConfigKey, ValueKind,
+};
+use crate::settings::{Setting, Source};
+use crate::registry::Registry;
+use crate::utils::open_in_editor;
+
+pub async fn execute(action: ConfigAction) -> Result<()> {
+    let path = get_config_path()?;
+    match action {
+        ConfigAction::Get { key } => get(&path, &key),
+        ConfigAction::Set { key, value } => set(&path, &key, &value),
+        ConfigAction::Unset { key } => unset(&path, &key),
+        ConfigAction::List => list(&path),
+        ConfigAction::Edit => edit(&path),
+        ConfigAction::Path => {
+            println!("{}", path.display());
+            Ok(())
+        }
+    }
+}
+
+fn get(path: &Path, key: &str) -> Result<()> {
+    let key = ConfigKey::parse(key)?;
+    let table = match active_profile() {
+        Some(profile) => apply_profile(read_table(path)?, &profile)?.0,
+        None => read_table(path)?,
+    };
+    let value = table_get(&table, &key.path).with_context(|| format!("'{}' is not set", key))?;
+    match value {
+        toml::Value::String(s) => println!("{}", s),
+        toml::Value::Table(t) => print!("{}", toml::to_string_pretty(t)?),
+        other => println!("{}", other),
+    }
+    Ok(())
+}
+
+fn set(path: &Path, key: &str, value: &str) -> Result<()> {
+    let before = read_table(path)?;
+    let mut table = before.clone();
+    let key = ConfigKey::parse(key)?;
+    let key = ConfigKey::from_path(&scope(&table, key.path, active_profile().as_ref()))?;
+    let value = parse_value(&key, value)?;
+
+    let (last, parents) = key.path.split_last().expect("keys are never empty");
+    let mut parent = &mut table;
+    for name in parents {
+        parent = parent
+            .entry(name.clone())
+            .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+            .as_table_mut()
+            .with_context(|| format!("'{}' in the config is not a table", name))?;
+    }
+    parent.insert(last.clone(), value.clone());
+
+    write_table(path, &before, table, &Registry::load()?)?;
+    println!(
+        "{} Set {} = {}",
+        "✓".bright_green(),
+        key.to_string().bright_cyan(),
+        value.to_string().bright_white()
+    );
+    Ok(())
+}
+
+fn unset(path: &Path, key: &str) -> Result<()> {
+    let before = read_table(path)?;
+    let mut table = before.clone();
+    // Unknown keys can still be removed, which is how typos get fixed
+    let parsed = ConfigKey::parse(key);
+    let keys = match &parsed {
+        Ok(key) => key.path.clone(),
+        Err(_) => key.split('.').map(str::to_string).collect(),
+    };
+    let keys = scope(&table, keys, active_profile().as_ref());
+    if !remove(&mut table, &keys) {
+        return match parsed {
+            Ok(_) => anyhow::bail!("'{}' is not set", keys.join(".")),
+            Err(e) => Err(e),
+        };
+    }
+
+    write_table(path, &before, table, &Registry::load()?)?;
+    println!("{} Unset {}", "✓".bright_green(), keys.join(".").bright_cyan());
+    Ok(())
+}
+
+fn list(path: &Path) -> Result<()> {
+    println!("{}", "⚙️  Configuration".bright_cyan().bold());
+    println!();
+    println!("{} File: {}", "→".bright_blue(), path.display().to_string().bright_white());
+    println!();
+
+    let table = read_table(path)?;
+    // With a profile, the values commands would see, marking its own
+    let (shown, overrides) = match active_profile() {
+        Some(profile) => {
+            let (resolved, overrides) = apply_profile(table.clone(), &profile)?;
+            println!(
+                "{} Profile: {} {}",
+                "→".bright_blue(),
+                profile.value.bright_yellow(),
+                format!("({})", profile.source).bright_black()
+            );
+            println!();
+            (resolved, Some(overrides))
+        }
+        None => (table.clone(), None),
+    };
+
+    let mut rows = Vec::new();
+    flatten(&shown, "", &mut rows);
+    if rows.is_empty() {
+        println!("  {}", "(empty)".bright_black());
+    }
+    for (key, value) in rows {
+        let value = match (key.as_str(), value) {
+            ("hf_token", toml::Value::String(token)) => {
+                format!("\"{}…\"", token.chars().take(4).collect::<String>())
+            }
+            (_, value) => value.to_string(),
+        };
+        let from_profile = overrides
+            .as_ref()
+            .is_some_and(|o| table_get(o, &ConfigKey::parse(&key).map(|k| k.path).unwrap_or_default()).is_some());
+        let marker = if from_profile { "  (profile)" } else { "" };
+        println!("  {} = {}{}", key.bright_cyan(), value.bright_white(), marker.bright_black());
+    }
+
+    println!();
+    report(&problems(&table, &Registry::load()?))?;
+    println!("{} Config is valid", "✓".bright_green());
+    Ok(())
+}
+
+fn edit(path: &Path) -> Result<()> {
+    if !path.exists() {
+        save_config(&Config::default())?;
+    }
+    open_in_editor(path)?;
+
+    // Catch mistakes now rather than at the next `yuy run`
+    report(&problems(&read_table(path)?, &Registry::load()?))
+        .map_err(|e| anyhow::anyhow!("{}: {}. Fix it with: yuy config edit", path.display(), e))?;
+    println!(
+        "{} Saved config {}",
+        "✓".bright_green(),
+        path.display().to_string().bright_white()
+    );
+    Ok(())
+}
+
+/// The file as written, so a config that fails validation can still be
+/// shown and fixed. Empty when there is no file; only `set`, `unset` and
+/// `edit` create one.
+fn read_table(path: &Path) -> Result<toml::Table> {
+    if !path.exists() {
+        return Ok(toml::Table::new());
+    }
+    let content = fs::read_to_string(path)?;
+    parse_config_file(path, &content)
+        .map_err(|e| anyhow::anyhow!("Invalid config {}: {}", path.display(), e.to_string().trim_end()))
+}
+
+/// Saves `table` over `before` unless it breaks the file or a profile
+/// that was fine, so an already broken config can still be fixed one key
+/// at a time. What is still broken is pointed out. Values are written as
+/// given, where a round trip through `Config` would print 0.7 as
+/// 0.699999988.
+fn write_table(path: &Path, before: &toml::Table, table: toml::Table, registry: &Registry) -> Result<()> {
+    let known = problems(before, registry);
+    let problems = problems(&table, registry);
+    if let Some(problem) = problems.iter().find(|(scope, _)| !known.contains_key(*scope)).map(|(_, p)| p) {
+        anyhow::bail!("{}", problem);
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, toml::to_string_pretty(&table)?)?;
+    for problem in problems.values() {
+        eprintln!("{} Still invalid: {}", "⚠".bright_yellow(), problem);
+    }
+    Ok(())
+}
+
+/// `table` with `profile` applied and the profiles themselves left out,
+/// and the profile's own overrides.
+fn apply_profile(mut table: toml::Table, profile: &Setting<String>) -> Result<(toml::Table, toml::Table)> {
+    let name = Config::with_profile(table.clone(), profile)?.profile.map(|p| p.value).unwrap_or_default();
+    let overrides = table_get(&table, &["profiles".to_string(), name])
+        .and_then(|p| p.as_table())
+        .cloned()
+        .unwrap_or_default();
+    table.remove("profiles");
+    overlay(&mut table, &overrides);
+    Ok((table, overrides))
+}
+
+/// Where `set` and `unset` write `key`: under the active profile, so they
+/// change what `get` shows. Keys that name a profile are left alone. An
+/// existing profile keeps its spelling; otherwise it is created.
+fn scope(table: &toml::Table, key: Vec<String>, profile: Option<&Setting<String>>) -> Vec<String> {
+    let Some(profile) = profile.filter(|_| key.first().is_some_and(|k| k != "profiles")) else {
+        return key;
+    };
+    let name = table
+        .get("profiles")
+        .and_then(|p| p.as_table())
+        .and_then(|profiles| profiles.keys().find(|n| n.eq_ignore_ascii_case(&profile.value)))
+        .cloned()
+        .unwrap_or_else(|| profile.value.clone());
+    ["profiles".to_string(), name].into_iter().chain(key).collect()
+}
+
+/// Everything `load_config` checks, plus the model names against the
+/// registry: the first problem in the file itself (keyed "") and in each
+/// profile applied over it (keyed "profiles.<name>").
+fn problems(table: &toml::Table, registry: &Registry) -> BTreeMap<String, String> {
+    let mut scopes = vec![(String::new(), Config::from_table(table.clone()))];
+    let profiles = table.get("profiles").and_then(|p| p.as_table()).cloned().unwrap_or_default();
+    for name in profiles.keys() {
+        let profile = Setting::new(name.clone(), Source::Config(format!("profiles.{}", name)));
+        scopes.push((format!("profiles.{}", name), Config::with_profile(table.clone(), &profile)));
+    }
+
+    let mut problems = BTreeMap::new();
+    for (scope, config) in scopes {
+        let checked = config.and_then(|config| {
+            let prefix = if scope.is_empty() { String::new() } else { format!("{}.", scope) };
+            let models = config.models.keys().map(|m| (format!("models.{}", m), m));
+            for (key, model) in config.default_model.iter().map(|m| ("default_model".to_string(), m)).chain(models) {
+                registry.resolve(model).map_err(|e| anyhow::anyhow!("{}{}: {}", prefix, key, e))?;
+            }
+            Ok(())
+        });
+        if let Err(e) = checked {
+            problems.insert(scope, e.to_string());
+        }
+    }
+    problems
+}
+
+/// Prints each problem; an error when there are any.
+fn report(problems: &BTreeMap<String, String>) -> Result<()> {
+    for problem in problems.values() {
+        eprintln!("{} {}", "✗".bright_red(), problem);
+    }
+    if !problems.is_empty() {
+        anyhow::bail!("{} problem(s) in the config", problems.len());
+    }
+    Ok(())
+}
+
+/// Parses a command-line value as the type `key` holds.
+fn parse_value(key: &ConfigKey, raw: &str) -> Result<toml::Value> {
+    let trimmed = raw.trim();
+    let value = match key.kind {
+        ValueKind::Text => toml::Value::String(raw.to_string()),
+        ValueKind::Integer => trimmed
+            .parse::<i64>()
+            .map(toml::Value::Integer)
+            .map_err(|_| anyhow::anyhow!("{} takes a whole number, not '{}'", key, raw))?,
+        ValueKind::Number => trimmed
+            .parse::<f64>()
+            .map(toml::Value::Float)
+            .map_err(|_| anyhow::anyhow!("{} takes a number, not '{}'", key, raw))?,
+        ValueKind::List => toml::Value::Array(
+            trimmed
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| toml::Value::String(item.to_string()))
+                .collect(),
+        ),
+        ValueKind::Table => anyhow::bail!(
+            "'{}' is a table. Set one of its keys instead, e.g. models.Yuuki-best.threads",
+            key
+        ),
+    };
+    Ok(value)
+}
+
+/// Removes `path`, and any tables that leaves empty. False when it isn't set.
+fn remove(table: &mut toml::Table, path: &[String]) -> bool {
+    let Some((first, rest)) = path.split_first() else {
+        return false;
+    };
+    if rest.is_empty() {
+        return table.remove(first).is_some();
+    }
+    let Some(child) = table.get_mut(first).and_then(|v| v.as_table_mut()) else {
+        return false;
+    };
+    let removed = remove(child, rest);
+    if child.is_empty() {
+        table.remove(first);
+    }
+    removed
+}
+
+/// `(dotted key, value)` for every value in `table`, sorted by key.
+fn flatten<'a>(table: &'a toml::Table, prefix: &str, rows: &mut Vec<(String, &'a toml::Value)>) {
+    for (key, value) in table {
+        let key = format!("{}{}", prefix, key);
+        match value {
+            toml::Value::Table(child) => flatten(child, &format!("{}.", key), rows),
+            value => rows.push((key, value)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(path: &[&str]) -> Vec<String> {
+        path.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_values_by_key_type() {
+        let parse = |k: &str, v: &str| parse_value(&ConfigKey::parse(k).unwrap(), v);
+        assert_eq!(parse("default_quant", "q8_0").unwrap(), toml::Value::String("q8_0".into()));
+        assert_eq!(parse("download_connections", "4").unwrap(), toml::Value::Integer(4));
+        assert_eq!(parse("presets.long.temperature", "0.9").unwrap(), toml::Value::Float(0.9));
+        let list = parse("runtimes", "ollama, llama-cpp").unwrap();
+        assert_eq!(list.as_array().unwrap().len(), 2);
+        assert!(parse("download_connections", "many").unwrap_err().to_string().contains("whole number"));
+        assert!(parse("models.Yuuki-best", "1").is_err());
+    }
+
+    #[test]
+    fn writes_under_the_active_profile() {
+        let table: toml::Table = toml::from_str("default_quant = \"q8_0\"\n[profiles.Laptop]\nruntimes = [\"ollama\"]\n").unwrap();
+        let laptop = Setting::new("laptop".to_string(), Source::Env("YUY_PROFILE"));
+        assert_eq!(scope(&table, key(&["default_quant"]), None), key(&["default_quant"]));
+        assert_eq!(
+            scope(&table, key(&["models", "Yuuki-best", "threads"]), Some(&laptop)),
+            key(&["profiles", "Laptop", "models", "Yuuki-best", "threads"])
+        );
+        let explicit = key(&["profiles", "desk", "default_quant"]);
+        assert_eq!(scope(&table, explicit.clone(), Some(&laptop)), explicit);
+        let travel = Setting::new("travel".to_string(), Source::Flag("--profile"));
+        assert_eq!(scope(&table, key(&["default_quant"]), Some(&travel)), key(&["profiles", "travel", "default_quant"]));
+
+        // Unsetting under the profile leaves the file's own value
+        let (quant, runtimes) = (
+            scope(&table, key(&["default_quant"]), Some(&laptop)),
+            scope(&table, key(&["runtimes"]), Some(&laptop)),
+        );
+        let mut table = table;
+        assert!(!remove(&mut table, &quant));
+        assert!(remove(&mut table, &runtimes));
+        assert!(table.get("default_quant").is_some() && table.get("profiles").is_none());
+    }
+
+    #[test]
+    fn fixes_a_broken_config_one_key_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let registry = Registry::builtin();
+        let broken: toml::Table = toml::from_str(
+            "default_quant = \"best\"\n[profiles.desk]\ndefault_runtime = \"vllm\"\n[profiles.laptop]\nruntimes = [\"ollama\"]\n",
+        )
+        .unwrap();
+        let problems_in = |table: &toml::Table| problems(table, &registry).into_keys().collect::<Vec<_>>();
+        assert_eq!(problems_in(&broken), ["", "profiles.desk", "profiles.laptop"]);
+
+        // The file's own value is fixed; the desk profile is still broken
+        let mut fixed = broken.clone();
+        fixed.insert("default_quant".into(), "q8_0".into());
+        write_table(&path, &broken, fixed.clone(), &registry).unwrap();
+        assert_eq!(problems_in(&read_table(&path).unwrap()), ["profiles.desk"]);
+
+        // Breaking what was fine is still refused
+        let mut worse = fixed.clone();
+        worse.insert("default_model".into(), "Yuuki-nope".into());
+        let err = write_table(&path, &fixed, worse, &registry).unwrap_err().to_string();
+        assert!(err.contains("default_model"), "{}", err);
+        assert_eq!(read_table(&path).unwrap(), fixed);
+
+        remove(&mut fixed, &key(&["profiles", "desk", "default_runtime"]));
+        write_table(&path, &broken, fixed, &registry).unwrap();
+        assert!(problems_in(&read_table(&path).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn reads_defaults_without_creating_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("yuuki/config.toml");
+        assert!(read_table(&path).unwrap().is_empty());
+        assert!(!dir.path().join("yuuki").exists());
+    }
+
+    #[test]
+    fn removes_keys_and_empty_tables() {
+        let mut table: toml::Table = toml::from_str(
+            "default_quant = \"q4_0\"\n[models.Yuuki-best]\nthreads = 4\n[presets.long]\nseed = 1\ntop_k = 40\n",
+        )
+        .unwrap();
+        assert!(remove(&mut table, &key(&["models", "Yuuki-best", "threads"])));
+        assert!(!table.contains_key("models"));
+        assert!(remove(&mut table, &key(&["presets", "long", "seed"])));
+        assert!(table_get(&table, &key(&["presets", "long", "top_k"])).is_some());
+        assert!(!remove(&mut table, &key(&["default_model"])));
+
+        let mut rows = Vec::new();
+        flatten(&table, "", &mut rows);
+        let keys: Vec<_> = rows.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(keys, ["default_quant", "presets.long.top_k"]);
+    }
+}
@@ -1,7 +1,12 @@
 use anyhow::Result;
 use colored::Colorize;
-use crate::config::{get_yuuki_dir, get_models_dir};
-use crate::utils::{command_exists, detect_platform, get_available_ram_gb, recommend_quantization};
+use crate::config::{active_profile, get_config_path, load_base_config, load_config, model_roots_in, models_in, Layout};
+use crate::hf::HfClient;
+use crate::registry::Registry;
+use crate::utils::{
+    command_exists, detect_platform, dir_size, format_size, free_disk_space, recommend_quant, Platform,
+    SystemInfo, Variant, DEFAULT_CONTEXT,
+};
 
 pub async fn execute() -> Result<()> {
     println!("{}", "🔍 Yuuki System Doctor".bright_cyan().bold());
@@ -27,19 +32,33 @@ pub async fn execute() -> Result<()> {
     );
 
     // RAM
-    let ram = get_available_ram_gb();
-    println!(
-        "  {} ~{} GB",
-        "RAM:".bright_white(),
-        ram
-    );
+    let system = SystemInfo::detect();
+    match (system.total_ram, system.available_ram) {
+        (Some(total), Some(available)) => println!(
+            "  {} {} total, {} available",
+            "RAM:".bright_white(),
+            format_size(total),
+            format_size(available)
+        ),
+        (Some(total), None) => println!("  {} {} total", "RAM:".bright_white(), format_size(total)),
+        _ => println!("  {} {}", "RAM:".bright_white(), "unknown".bright_yellow()),
+    }
 
-    // Recommended quantization
-    let recommended_quant = recommend_quantization(platform.clone(), ram);
+    // CPU
+    let features = &system.cpu_features;
+    let simd: Vec<&str> = [
+        ("AVX2", features.avx2),
+        ("AVX-512", features.avx512),
+        ("NEON", features.neon),
+    ]
+    .into_iter()
+    .filter_map(|(name, present)| present.then_some(name))
+    .collect();
     println!(
-        "  {} {}",
-        "Recommended quantization:".bright_white(),
-        recommended_quant.bright_green()
+        "  {} {} cores{}",
+        "CPU:".bright_white(),
+        system.cpu_cores,
+        if simd.is_empty() { String::new() } else { format!(", {}", simd.join(" ")) }
     );
 
     println!();
@@ -47,54 +66,78 @@ pub async fn execute() -> Result<()> {
     // Yuuki directories
     println!("{}", "Yuuki Configuration:".bright_cyan());
     
-    let yuuki_dir = get_yuuki_dir()?;
+    let layout = Layout::current()?;
     println!(
-        "  {} {}",
+        "  {} {} {}",
         "Config dir:".bright_white(),
-        yuuki_dir.display().to_string().bright_yellow()
-    );
-
-    let models_dir = get_models_dir()?;
-    println!(
-        "  {} {}",
-        "Models dir:".bright_white(),
-        models_dir.display().to_string().bright_yellow()
+        layout.value.config.display().to_string().bright_yellow(),
+        format!("({})", layout.source).bright_black()
     );
-
-    // Check disk space
-    if models_dir.exists() {
-        let mut total_size = 0u64;
-        let mut model_count = 0;
-
-        for entry in std::fs::read_dir(&models_dir)? {
-            if let Ok(dir_entry) = entry {
-                if dir_entry.path().is_dir() {
-                    model_count += 1;
-                    // Calculate size
-                    if let Ok(files) = std::fs::read_dir(dir_entry.path()) {
-                        for file in files {
-                            if let Ok(f) = file {
-                                if let Ok(metadata) = f.metadata() {
-                                    total_size += metadata.len();
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
+    if let Some(profile) = active_profile() {
         println!(
-            "  {} {}",
-            "Models downloaded:".bright_white(),
-            model_count
+            "  {} {} {}",
+            "Profile:".bright_white(),
+            profile.value.bright_yellow(),
+            format!("({})", profile.source).bright_black()
         );
+    }
+    if layout.value.is_split() {
+        println!("  {} {}", "Data dir:".bright_white(), layout.value.data.display().to_string().bright_yellow());
+        println!("  {} {}", "Cache dir:".bright_white(), layout.value.cache.display().to_string().bright_yellow());
+    }
+
+    // A bad config fails every other command, so say so here instead
+    let config_path = get_config_path()?;
+    let config = load_config();
+    match &config {
+        Ok(_) if !config_path.exists() => println!(
+            "  {} {} {}",
+            "Config file:".bright_white(),
+            config_path.display().to_string().bright_yellow(),
+            "(not created yet, using defaults)".bright_black()
+        ),
+        Ok(_) => println!(
+            "  {} {} {}",
+            "Config file:".bright_white(),
+            config_path.display().to_string().bright_yellow(),
+            "✓".bright_green()
+        ),
+        Err(e) => println!("  {} {} {}", "Config file:".bright_white(), "✗".bright_red(), e.to_string().bright_red()),
+    }
+
+    // Each storage root with its models and the space left on its disk.
+    // A broken profile still leaves the file's roots; a broken file, the default.
+    let fallback = load_base_config().unwrap_or_default();
+    let roots = model_roots_in(config.as_ref().unwrap_or(&fallback))?;
+    let mut model_count = 0;
+    for root in &roots {
+        let models = models_in(root)?;
+        let size: u64 = models.iter().map(|(_, dir)| dir_size(dir)).sum();
+        model_count += models.len();
+        let free = free_disk_space(root)
+            .map(|free| format!(", {} free", format_size(free)))
+            .unwrap_or_default();
         println!(
-            "  {} {}",
-            "Total size:".bright_white(),
-            crate::utils::format_size(total_size).bright_green()
+            "  {} {} {}",
+            "Models dir:".bright_white(),
+            root.display().to_string().bright_yellow(),
+            format!("({} model(s), {}{})", models.len(), format_size(size), free).bright_black()
         );
     }
+    if roots.len() > 1 {
+        println!("  {} {}", "Models downloaded:".bright_white(), model_count);
+    }
+    // Downloads go to the first root, so that's the space that limits them
+    let free_disk = free_disk_space(&roots[0]);
+
+    println!();
+
+    // Recommended quantization per model
+    println!(
+        "{}",
+        format!("Recommended Quantization ({} token context):", DEFAULT_CONTEXT).bright_cyan()
+    );
+    recommend_for_models(&system, &platform, free_disk).await?;
 
     println!();
 
@@ -103,10 +146,14 @@ pub async fn execute() -> Result<()> {
 
     let llama_installed = command_exists("llama-cli")
         || command_exists("llama")
-        || command_exists("main");
+        || command_exists("main")
+        || command_exists("llama-server");
 
     if llama_installed {
         println!("  {} {} {}", "✓".bright_green(), "llama.cpp".bright_white(), "installed".bright_green());
+        if !command_exists("llama-server") {
+            println!("    {} llama-server not found; chat falls back to llama-cli", "→".bright_yellow());
+        }
     } else {
         println!("  {} {} {}", "✗".bright_red(), "llama.cpp".bright_white(), "not installed".bright_red());
     }
@@ -170,3 +217,50 @@ fn check_command(cmd: &str) {
         println!("  {} {} {}", "✗".bright_yellow(), cmd.bright_white(), "not found".bright_yellow());
     }
 }
+
+async fn recommend_for_models(system: &SystemInfo, platform: &Platform, free_disk: Option<u64>) -> Result<()> {
+    let hf = HfClient::new();
+
+    for entry in Registry::load()?.models() {
+        // Registry sizes first, so this works offline
+        let mut variants: Vec<Variant> = entry
+            .quants
+            .iter()
+            .map(|q| Variant { quant: q.name.clone(), size: q.size })
+            .collect();
+        if variants.is_empty() {
+            if let Ok(files) = hf.list_gguf_files(entry.download_repo()).await {
+                variants = files
+                    .into_iter()
+                    .map(|f| Variant { quant: f.quant, size: f.size })
+                    .collect();
+            }
+        }
+
+        match recommend_quant(
+            &variants,
+            entry.parameters.as_deref(),
+            DEFAULT_CONTEXT,
+            system,
+            platform,
+            free_disk,
+        ) {
+            Some(rec) => {
+                let quant = if rec.fits {
+                    rec.quant.bright_green()
+                } else {
+                    format!("{} (may not fit)", rec.quant).bright_yellow()
+                };
+                println!("  {} {}", format!("{}:", entry.name).bright_white(), quant);
+                println!("    {}", rec.reason.bright_black());
+            }
+            None => println!(
+                "  {} {}",
+                format!("{}:", entry.name).bright_white(),
+                "no GGUF sizes known".bright_black()
+            ),
+        }
+    }
+
+    Ok(())
+}
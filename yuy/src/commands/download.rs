@@ -2,44 +2,64 @@ use anyhow::{Context, Result};
 use colored::Colorize;
 use futures_util::StreamExt;
 use indicatif::{ProgressBar, ProgressStyle};
-use std::fs::File;
-use std::io::Write;
-use crate::config::{get_models_dir, HF_ORG, YUUKI_MODELS};
-use crate::utils::{detect_platform, get_available_ram_gb, recommend_quantization};
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, RANGE};
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::fs::{File, OpenOptions};
+use std::io::{Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use crate::hf::{pick_quant, GgufFile, HfClient};
+use crate::config::model_dir;
+use crate::registry::{ModelEntry, Registry};
+use crate::settings::{print_explain, Setting, Settings, Source, DEFAULT_QUANT};
+use crate::utils::{
+    detect_platform, free_disk_space, recommend_quant, SystemInfo, Variant, DEFAULT_CONTEXT,
+};
 
-pub async fn execute(model: &str, quant: Option<String>) -> Result<()> {
-    println!("{}", "📥 Yuuki Model Downloader".bright_cyan().bold());
-    println!();
+pub async fn execute(model: Option<String>, quant: Option<String>, connections: Option<usize>, explain: bool) -> Result<()> {
+    if !explain {
+        println!("{}", "📥 Yuuki Model Downloader".bright_cyan().bold());
+        println!();
+    }
 
     // Validate model name
-    if !YUUKI_MODELS.contains(&model) {
-        println!(
-            "{} Model '{}' not found",
-            "✗".bright_red(),
-            model.bright_yellow()
-        );
-        println!("\n{}", "Available models:".bright_cyan());
-        for m in YUUKI_MODELS {
-            println!("  • {}", m.bright_green());
-        }
-        return Ok(());
-    }
+    let settings = Settings::load()?;
+    let model_setting = settings.model(model);
+    let model_ref = Registry::load()?.resolve(&model_setting.value)?;
+    let entry = &model_ref.entry;
+    let model = entry.name.as_str();
+    let quant = settings.quant(model_ref.quant_setting(quant)?);
+
+    // List the repo first; its file sizes drive the auto-selection
+    let hf = HfClient::new();
+    let repo = entry.download_repo();
+    let listing = hf.list_gguf_files(repo).await;
+    // Another quant of a model goes next to the ones already downloaded
+    let model_dir = model_dir(model)?;
 
     // Determine quantization
-    let quantization = if let Some(q) = quant {
-        q
-    } else {
-        let platform = detect_platform();
-        let ram = get_available_ram_gb();
-        let recommended = recommend_quantization(platform, ram);
-        println!(
-            "{} Auto-selected quantization: {} (based on your system)",
-            "ℹ".bright_blue(),
-            recommended.bright_green()
-        );
-        recommended.to_string()
+    let quant = match quant {
+        Some(q) => q,
+        None => auto_select_quant(entry, listing.as_deref().ok(), &model_dir, explain),
+    };
+    let connections = match (connections, settings.config().download_connections) {
+        (Some(n), _) => Setting::new(n, Source::Flag("--connections")),
+        (None, Some(n)) => Setting::new(n, settings.config_source("download_connections")),
+        (None, None) => Setting::new(1, Source::Default),
     };
 
+    if explain {
+        let mut rows: Vec<_> = settings.profile().into_iter().collect();
+        rows.extend([
+            ("model", model_setting),
+            ("quant", quant),
+            ("connections", Setting::new(connections.value.to_string(), connections.source)),
+        ]);
+        print_explain(&rows);
+        return Ok(());
+    }
+    let quantization = quant.value;
+
     println!(
         "{} Model: {}",
         "→".bright_blue(),
@@ -51,90 +71,735 @@ pub async fn execute(model: &str, quant: Option<String>) -> Result<()> {
         quantization.bright_green()
     );
     println!(
-        "{} Source: {}/{}",
+        "{} Source: {}",
         "→".bright_blue(),
-        HF_ORG.bright_yellow(),
-        model.bright_yellow()
+        entry.download_repo().bright_yellow()
     );
     println!();
 
+    // Resolve the real file name from the repo listing
+    let filename = match listing {
+        Ok(files) => {
+            let Some(file) = pick_quant(&files, &quantization) else {
+                if files.is_empty() {
+                    anyhow::bail!("No GGUF files found in {}", repo);
+                }
+                let available: Vec<_> = files.iter().map(|f| f.quant.as_str()).collect();
+                anyhow::bail!(
+                    "Quantization '{}' is not available in {}. Available: {}",
+                    quantization,
+                    repo,
+                    available.join(", ")
+                );
+            };
+            file.filename.clone()
+        }
+        Err(e) => {
+            // The listing API can be unreachable while the file CDN isn't
+            if !entry.quants.is_empty() && entry.quant(&quantization).is_none() {
+                let known: Vec<_> = entry.quants.iter().map(|q| q.name.as_str()).collect();
+                anyhow::bail!(
+                    "Quantization '{}' is not available for {}. Known: {}",
+                    quantization,
+                    model,
+                    known.join(", ")
+                );
+            }
+            let filename = entry.filename(&quantization);
+            println!(
+                "{} Could not list {} ({}), trying {}",
+                "⚠".bright_yellow(),
+                repo,
+                e,
+                filename.bright_yellow()
+            );
+            filename
+        }
+    };
+    let url = hf.resolve_url(repo, &filename);
+
     // Create model directory
-    let models_dir = get_models_dir()?;
-    let model_dir = models_dir.join(model);
     std::fs::create_dir_all(&model_dir)?;
 
-    // Construct Hugging Face URL
-    let filename = format!("{}-{}.gguf", model.to_lowercase(), quantization);
-    let url = format!(
-        "https://huggingface.co/{}/{}/resolve/main/{}",
-        HF_ORG, model, filename
-    );
+    let output_path = model_dir.join(&filename);
+    if output_path.exists() {
+        println!(
+            "{} {} is already downloaded.",
+            "ℹ".bright_blue(),
+            filename.bright_yellow()
+        );
+        return Ok(());
+    }
 
     println!("{} Downloading from Hugging Face...", "↓".bright_cyan());
     println!("{} URL: {}", "  ".bright_black(), url.bright_black());
     println!();
 
     // Download file with progress bar
-    let client = reqwest::Client::new();
-    let response = client
-        .get(&url)
-        .send()
-        .await
-        .context("Failed to start download")?;
+    let downloaded = fetch_resumable(&hf, &url, &output_path, connections.value.max(1)).await?;
+    println!();
 
-    if !response.status().is_success() {
+    verify_download(&hf, repo, &filename, &output_path, &downloaded.sha256).await?;
+
+    println!(
+        "{} Model downloaded successfully!",
+        "✓".bright_green().bold()
+    );
+    println!(
+        "  {} {}",
+        "Location:".bright_cyan(),
+        output_path.display().to_string().bright_yellow()
+    );
+    println!(
+        "  {} {}",
+        "Size:".bright_cyan(),
+        crate::utils::format_size(downloaded.size).bright_yellow()
+    );
+    println!();
+    println!(
+        "{} Run the model with: {}",
+        "→".bright_blue(),
+        format!("yuy run {}", model).bright_green()
+    );
+
+    Ok(())
+}
+
+/// Picks the best quantization that fits this machine and says why.
+/// Sizes come from the repo listing, or the registry when it's unreachable.
+/// Nothing is printed when `quiet`.
+fn auto_select_quant(entry: &ModelEntry, listing: Option<&[GgufFile]>, model_dir: &Path, quiet: bool) -> Setting<String> {
+    let variants: Vec<Variant> = match listing {
+        Some(files) if !files.is_empty() => files
+            .iter()
+            .map(|f| Variant { quant: f.quant.clone(), size: f.size })
+            .collect(),
+        _ => entry
+            .quants
+            .iter()
+            .map(|q| Variant { quant: q.name.clone(), size: q.size })
+            .collect(),
+    };
+
+    let recommendation = recommend_quant(
+        &variants,
+        entry.parameters.as_deref(),
+        DEFAULT_CONTEXT,
+        &SystemInfo::detect(),
+        &detect_platform(),
+        free_disk_space(model_dir),
+    );
+
+    match recommendation {
+        Some(rec) if rec.fits => {
+            if !quiet {
+                println!(
+                    "{} Auto-selected quantization: {}",
+                    "ℹ".bright_blue(),
+                    rec.quant.bright_green()
+                );
+                println!("  {}", rec.reason.bright_black());
+            }
+            Setting::new(rec.quant, Source::Auto("best fit for this machine".to_string()))
+        }
+        Some(rec) => {
+            if !quiet {
+                println!(
+                    "{} Auto-selected quantization: {} (may not fit)",
+                    "⚠".bright_yellow(),
+                    rec.quant.bright_yellow()
+                );
+                println!("  {}", rec.reason.bright_black());
+            }
+            Setting::new(rec.quant, Source::Auto("smallest variant, may not fit".to_string()))
+        }
+        None => {
+            let fallback = DEFAULT_QUANT;
+            if !quiet {
+                println!(
+                    "{} Auto-selected quantization: {} (variant sizes unknown)",
+                    "ℹ".bright_blue(),
+                    fallback.bright_green()
+                );
+            }
+            Setting::new(fallback.to_string(), Source::Auto("variant sizes unknown".to_string()))
+        }
+    }
+}
+
+/// Compares a finished download against the LFS SHA256 that Hugging Face
+/// publishes for it. A mismatching file is deleted.
+async fn verify_download(
+    hf: &HfClient,
+    repo: &str,
+    filename: &str,
+    path: &Path,
+    sha256: &str,
+) -> Result<()> {
+    let expected = match hf.lfs_sha256(repo, filename).await {
+        Ok(Some(oid)) => oid,
+        Ok(None) => {
+            println!(
+                "{} No checksum published for {}, skipping verification",
+                "⚠".bright_yellow(),
+                filename.bright_yellow()
+            );
+            return Ok(());
+        }
+        Err(e) => {
+            println!(
+                "{} Could not fetch checksum, skipping verification: {}",
+                "⚠".bright_yellow(),
+                e
+            );
+            return Ok(());
+        }
+    };
+
+    if !expected.eq_ignore_ascii_case(sha256) {
+        std::fs::remove_file(path)?;
         anyhow::bail!(
-            "Failed to download: HTTP {} - Model file might not exist yet. Try checking HuggingFace.",
-            response.status()
+            "Checksum mismatch for {}\n  expected: {}\n  actual:   {}\nThe file was deleted. Run the download again.",
+            filename,
+            expected,
+            sha256
         );
     }
 
-    let total_size = response
-        .content_length()
-        .context("Failed to get content length")?;
+    println!("{} SHA256 verified", "✓".bright_green());
+    Ok(())
+}
+
+/// A completed download.
+pub struct Downloaded {
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Hex-encoded SHA256 of a file's content.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Path of the in-progress file for `output_path` (`<file>.part`).
+fn part_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    output_path.with_file_name(name)
+}
+
+/// Total length from a `Content-Range: bytes <start>-<end>/<total>` header.
+fn content_range_total(response: &reqwest::Response) -> Option<u64> {
+    let value = response.headers().get(CONTENT_RANGE)?.to_str().ok()?;
+    value.rsplit('/').next()?.trim().parse().ok()
+}
 
-    let pb = ProgressBar::new(total_size);
+/// Marker left next to `<file>.part` while a parallel download is running.
+/// It holds the length of the prefix that was already valid when the run
+/// started, since the preallocated part file can't be trusted past it.
+fn parallel_marker_path(part: &Path) -> PathBuf {
+    let mut name = part.file_name().unwrap_or_default().to_os_string();
+    name.push(".parallel");
+    part.with_file_name(name)
+}
+
+/// Truncates a part file left behind by a parallel download that was killed
+/// before it could clean up, so that it can be resumed like any other.
+fn recover_parallel_part(part: &Path) -> Result<()> {
+    let marker = parallel_marker_path(part);
+    if !marker.exists() {
+        return Ok(());
+    }
+
+    let valid: u64 = std::fs::read_to_string(&marker)?.trim().parse().unwrap_or(0);
+    if part.exists() {
+        OpenOptions::new().write(true).open(part)?.set_len(valid)?;
+    }
+    std::fs::remove_file(&marker)?;
+    Ok(())
+}
+
+fn progress_bar(total_size: u64, position: u64) -> Result<ProgressBar> {
+    let pb = ProgressBar::new(total_size).with_position(position);
     pb.set_style(
         ProgressStyle::default_bar()
             .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")?
             .progress_chars("#>-"),
     );
+    Ok(pb)
+}
 
-    let output_path = model_dir.join(&filename);
-    let mut file = File::create(&output_path)?;
-    let mut downloaded: u64 = 0;
+/// Downloads `url` into `output_path`, going through `<file>.part`.
+///
+/// If a partial file is left over from an interrupted run, only the missing
+/// bytes are requested with a `Range` header. The file is renamed into place
+/// once all bytes have arrived. With more than one connection, and when the
+/// server advertises `Accept-Ranges: bytes`, the missing bytes are split
+/// into ranges fetched concurrently.
+pub async fn fetch_resumable(
+    hf: &HfClient,
+    url: &str,
+    output_path: &Path,
+    connections: usize,
+) -> Result<Downloaded> {
+    let part = part_path(output_path);
+    recover_parallel_part(&part)?;
+
+    if connections > 1 {
+        if let Some(total_size) = probe_range_support(hf.http(), url).await? {
+            let offset = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+            if offset < total_size {
+                return fetch_parallel(hf.http(), url, output_path, total_size, offset, connections).await;
+            }
+        }
+    }
+
+    fetch_single(hf, url, output_path).await
+}
+
+/// Returns the file size if the server accepts byte ranges for `url`.
+async fn probe_range_support(client: &reqwest::Client, url: &str) -> Result<Option<u64>> {
+    let response = client.head(url).send().await.context("Failed to start download")?;
+    if !response.status().is_success() {
+        return Ok(None);
+    }
+
+    let headers = response.headers();
+    let accepts_ranges = headers
+        .get(ACCEPT_RANGES)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("bytes"));
+    let total_size = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok());
+
+    Ok(total_size.filter(|_| accepts_ranges))
+}
+
+async fn fetch_single(hf: &HfClient, url: &str, output_path: &Path) -> Result<Downloaded> {
+    let part = part_path(output_path);
+
+    let (response, offset) = loop {
+        let offset = std::fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+
+        let mut request = hf.http().get(url);
+        if offset > 0 {
+            request = request.header(RANGE, format!("bytes={}-", offset));
+        }
+        let response = request.send().await.context("Failed to start download")?;
+
+        if response.status() == StatusCode::RANGE_NOT_SATISFIABLE && offset > 0 {
+            // Either the previous run stopped right before the rename, or the
+            // partial file no longer matches the remote one.
+            if content_range_total(&response) == Some(offset) {
+                let sha256 = sha256_file(&part)?;
+                std::fs::rename(&part, output_path)?;
+                return Ok(Downloaded { size: offset, sha256 });
+            }
+            std::fs::remove_file(&part)?;
+            continue;
+        }
+
+        break (response, offset);
+    };
+
+    if let Some(e) = hf.auth_error(response.status(), url) {
+        return Err(e);
+    }
+    if !response.status().is_success() {
+        anyhow::bail!(
+            "Failed to download: HTTP {} - Model file might not exist yet. Try checking HuggingFace.",
+            response.status()
+        );
+    }
+
+    // A plain 200 means the server ignored the range, so start from scratch.
+    let (mut file, offset, total_size) = if response.status() == StatusCode::PARTIAL_CONTENT {
+        let total = content_range_total(&response)
+            .context("Server sent a partial response without a valid Content-Range")?;
+        let file = OpenOptions::new().append(true).open(&part)?;
+        (file, offset, total)
+    } else {
+        let total = response
+            .content_length()
+            .context("Failed to get content length")?;
+        (File::create(&part)?, 0, total)
+    };
+
+    if offset > 0 {
+        println!(
+            "{} Resuming from {}",
+            "↻".bright_cyan(),
+            crate::utils::format_size(offset).bright_yellow()
+        );
+    }
+
+    // Hash while streaming; a resumed download first re-reads what it has.
+    let mut hasher = Sha256::new();
+    if offset > 0 {
+        std::io::copy(&mut File::open(&part)?, &mut hasher)?;
+    }
+
+    let pb = progress_bar(total_size, offset)?;
+    let mut downloaded = offset;
     let mut stream = response.bytes_stream();
 
     while let Some(item) = stream.next().await {
-        let chunk = item.context("Error while downloading file")?;
+        let chunk = match item {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                pb.abandon();
+                file.flush()?;
+                return Err(anyhow::Error::new(e).context(
+                    "Download interrupted. Run the same command again to resume",
+                ));
+            }
+        };
         file.write_all(&chunk)?;
+        hasher.update(&chunk);
         downloaded += chunk.len() as u64;
         pb.set_position(downloaded);
     }
+    file.flush()?;
+
+    if downloaded != total_size {
+        pb.abandon();
+        anyhow::bail!(
+            "Download incomplete ({} of {}). Run the same command again to resume",
+            crate::utils::format_size(downloaded),
+            crate::utils::format_size(total_size)
+        );
+    }
 
     pb.finish_with_message("Download complete!");
-    println!();
+    std::fs::rename(&part, output_path)?;
 
+    Ok(Downloaded {
+        size: total_size,
+        sha256: format!("{:x}", hasher.finalize()),
+    })
+}
+
+/// Fetches `offset..total_size` as `connections` concurrent range requests,
+/// each writing at its own position in a preallocated part file.
+async fn fetch_parallel(
+    client: &reqwest::Client,
+    url: &str,
+    output_path: &Path,
+    total_size: u64,
+    offset: u64,
+    connections: usize,
+) -> Result<Downloaded> {
+    let part = part_path(output_path);
+    let marker = parallel_marker_path(&part);
+
+    std::fs::write(&marker, offset.to_string())?;
+    OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&part)?
+        .set_len(total_size)?;
+
+    if offset > 0 {
+        println!(
+            "{} Resuming from {}",
+            "↻".bright_cyan(),
+            crate::utils::format_size(offset).bright_yellow()
+        );
+    }
     println!(
-        "{} Model downloaded successfully!",
-        "✓".bright_green().bold()
-    );
-    println!(
-        "  {} {}",
-        "Location:".bright_cyan(),
-        output_path.display().to_string().bright_yellow()
-    );
-    println!(
-        "  {} {}",
-        "Size:".bright_cyan(),
-        crate::utils::format_size(total_size).bright_yellow()
-    );
-    println!();
-    println!(
-        "{} Run the model with: {}",
-        "→".bright_blue(),
-        format!("yuy run {}", model).bright_green()
+        "{} Using {} connections",
+        "⇉".bright_cyan(),
+        connections.to_string().bright_yellow()
     );
 
-    Ok(())
+    let pb = progress_bar(total_size, offset)?;
+    let remaining = total_size - offset;
+    let chunk_size = remaining.div_ceil(connections as u64);
+
+    let mut ranges = Vec::new();
+    let mut start = offset;
+    while start < total_size {
+        let end = (start + chunk_size).min(total_size);
+        ranges.push((start, end));
+        start = end;
+    }
+
+    let handles: Vec<_> = ranges
+        .iter()
+        .map(|&(start, end)| {
+            tokio::spawn(fetch_range(
+                client.clone(),
+                url.to_string(),
+                part.clone(),
+                start,
+                end,
+                pb.clone(),
+            ))
+        })
+        .collect();
+
+    // Track how far each range got so a failed run can keep the longest
+    // contiguous prefix and resume from there.
+    let mut valid = offset;
+    let mut contiguous = true;
+    let mut first_error = None;
+    for (handle, &(start, end)) in handles.into_iter().zip(&ranges) {
+        let (written, result) = match handle.await {
+            Ok(outcome) => outcome,
+            Err(e) => (0, Err(e.into())),
+        };
+        if contiguous {
+            valid = start + written;
+            contiguous = result.is_ok() && start + written == end;
+        }
+        if let Err(e) = result {
+            first_error.get_or_insert(e);
+        }
+    }
+
+    if let Some(e) = first_error {
+        pb.abandon();
+        OpenOptions::new().write(true).open(&part)?.set_len(valid)?;
+        std::fs::remove_file(&marker)?;
+        return Err(e.context("Download interrupted. Run the same command again to resume"));
+    }
+
+    pb.finish_with_message("Download complete!");
+    std::fs::remove_file(&marker)?;
+
+    // Ranges arrive out of order, so hash the assembled file instead.
+    let sha256 = sha256_file(&part)?;
+    std::fs::rename(&part, output_path)?;
+
+    Ok(Downloaded { size: total_size, sha256 })
+}
+
+/// Downloads `start..end` into `part` at the same offset. Returns how many
+/// bytes were written alongside the outcome, so partial progress is known
+/// even on failure.
+async fn fetch_range(
+    client: reqwest::Client,
+    url: String,
+    part: PathBuf,
+    start: u64,
+    end: u64,
+    pb: ProgressBar,
+) -> (u64, Result<()>) {
+    let mut written = 0u64;
+    let result = async {
+        let mut file = OpenOptions::new().write(true).open(&part)?;
+        file.seek(SeekFrom::Start(start))?;
+
+        let response = client
+            .get(&url)
+            .header(RANGE, format!("bytes={}-{}", start, end - 1))
+            .send()
+            .await
+            .context("Failed to start download")?;
+        if response.status() != StatusCode::PARTIAL_CONTENT {
+            anyhow::bail!("Server did not honor range request: HTTP {}", response.status());
+        }
+
+        let mut stream = response.bytes_stream();
+        while let Some(item) = stream.next().await {
+            let chunk = item.context("Error while downloading file")?;
+            let len = (chunk.len() as u64).min(end - start - written);
+            file.write_all(&chunk[..len as usize])?;
+            written += len;
+            pb.inc(len);
+        }
+        file.flush()?;
+
+        if start + written != end {
+            anyhow::bail!("Connection closed before range {}-{} was complete", start, end - 1);
+        }
+        Ok(())
+    }
+    .await;
+
+    (written, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{self, Request, Response};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    /// Serves `body` at `/model.gguf`. When `drop_first_after` is set, the
+    /// first GET advertises its full length but is closed after that many
+    /// bytes.
+    async fn serve(body: Vec<u8>, honor_range: bool, drop_first_after: Option<usize>) -> String {
+        let first_get = AtomicBool::new(true);
+        let base = test_support::serve(move |request| {
+            let response = respond(request, &body, honor_range);
+            match drop_first_after {
+                Some(n) if request.method == "GET" && first_get.swap(false, Ordering::SeqCst) => response.cut_after(n),
+                _ => response,
+            }
+        })
+        .await;
+        format!("{}/model.gguf", base)
+    }
+
+    fn respond(request: &Request, body: &[u8], honor_range: bool) -> Response {
+        let range = request.header("range").and_then(|r| r.strip_prefix("bytes=")).filter(|_| honor_range).map(|r| {
+            let (start, end) = r.split_once('-').unwrap();
+            let start: usize = start.parse().unwrap();
+            let end = end.parse::<usize>().map(|e| e + 1).unwrap_or(body.len());
+            (start, end.min(body.len()))
+        });
+
+        let response = match range {
+            Some((start, _)) if start >= body.len() => {
+                return Response::new("416 Range Not Satisfiable", Vec::new()).header("Content-Range", format!("bytes */{}", body.len()));
+            }
+            Some((start, end)) => Response::new("206 Partial Content", &body[start..end])
+                .header("Content-Range", format!("bytes {}-{}/{}", start, end - 1, body.len())),
+            None => Response::ok(body),
+        };
+        if honor_range {
+            response.header("Accept-Ranges", "bytes")
+        } else {
+            response
+        }
+    }
+
+    fn sha256_hex(data: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(data))
+    }
+
+    fn sample_body() -> Vec<u8> {
+        (0..64 * 1024).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[tokio::test]
+    async fn resumes_after_dropped_connection() {
+        let body = sample_body();
+        let url = serve(body.clone(), true, Some(20_000)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+        let hf = HfClient::with_endpoint("http://unused", None);
+
+        assert!(fetch_resumable(&hf, &url, &output, 1).await.is_err());
+        assert!(!output.exists());
+        let partial = std::fs::metadata(part_path(&output)).unwrap().len();
+        assert!(partial > 0 && partial < body.len() as u64);
+
+        let downloaded = fetch_resumable(&hf, &url, &output, 1).await.unwrap();
+        assert_eq!(downloaded.size, body.len() as u64);
+        assert_eq!(downloaded.sha256, sha256_hex(&body));
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+        assert!(!part_path(&output).exists());
+    }
+
+    #[tokio::test]
+    async fn restarts_when_server_ignores_range() {
+        let body = sample_body();
+        let url = serve(body.clone(), false, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+        std::fs::write(part_path(&output), b"stale bytes").unwrap();
+
+        let hf = HfClient::with_endpoint("http://unused", None);
+        fetch_resumable(&hf, &url, &output, 1).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn finalizes_part_file_that_is_already_complete() {
+        let body = sample_body();
+        let url = serve(body.clone(), true, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+        std::fs::write(part_path(&output), &body).unwrap();
+
+        let hf = HfClient::with_endpoint("http://unused", None);
+        let downloaded = fetch_resumable(&hf, &url, &output, 1).await.unwrap();
+        assert_eq!(downloaded.size, body.len() as u64);
+        assert_eq!(downloaded.sha256, sha256_hex(&body));
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn parallel_download_reassembles_ranges() {
+        let body = sample_body();
+        let url = serve(body.clone(), true, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+
+        let hf = HfClient::with_endpoint("http://unused", None);
+        let downloaded = fetch_resumable(&hf, &url, &output, 4).await.unwrap();
+        assert_eq!(downloaded.size, body.len() as u64);
+        assert_eq!(downloaded.sha256, sha256_hex(&body));
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+        assert!(!parallel_marker_path(&part_path(&output)).exists());
+    }
+
+    #[tokio::test]
+    async fn parallel_download_keeps_contiguous_prefix_on_failure() {
+        let body = sample_body();
+        let url = serve(body.clone(), true, Some(1_000)).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+        let hf = HfClient::with_endpoint("http://unused", None);
+
+        assert!(fetch_resumable(&hf, &url, &output, 4).await.is_err());
+        let partial = std::fs::metadata(part_path(&output)).unwrap().len();
+        assert!(partial < body.len() as u64);
+        assert_eq!(std::fs::read(part_path(&output)).unwrap(), &body[..partial as usize]);
+
+        fetch_resumable(&hf, &url, &output, 4).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn parallel_falls_back_without_accept_ranges() {
+        let body = sample_body();
+        let url = serve(body.clone(), false, None).await;
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+
+        let hf = HfClient::with_endpoint("http://unused", None);
+        fetch_resumable(&hf, &url, &output, 4).await.unwrap();
+        assert_eq!(std::fs::read(&output).unwrap(), body);
+    }
+
+    #[tokio::test]
+    async fn deletes_file_with_mismatching_checksum() {
+        let tree = r#"[{"type":"file","path":"model.gguf","lfs":{"oid":"0badc0de","size":4}}]"#;
+        let hf = HfClient::with_endpoint(&test_support::serve(move |_| Response::ok(tree)).await, None);
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("model.gguf");
+        std::fs::write(&output, b"gguf").unwrap();
+
+        let err = verify_download(&hf, "org/repo", "model.gguf", &output, &sha256_hex(b"gguf"))
+            .await
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("Checksum mismatch"), "{}", err);
+        assert!(!output.exists());
+
+        std::fs::write(&output, b"gguf").unwrap();
+        verify_download(&hf, "org/repo", "model.gguf", &output, "0BADC0DE").await.unwrap();
+        assert!(output.exists());
+    }
+
+    #[test]
+    fn recovers_part_file_from_killed_parallel_run() {
+        let dir = tempfile::tempdir().unwrap();
+        let part = part_path(&dir.path().join("model.gguf"));
+        std::fs::write(&part, vec![7u8; 4096]).unwrap();
+        std::fs::write(parallel_marker_path(&part), "1024").unwrap();
+
+        recover_parallel_part(&part).unwrap();
+        assert_eq!(std::fs::metadata(&part).unwrap().len(), 1024);
+        assert!(!parallel_marker_path(&part).exists());
+    }
 }
@@ -1,27 +1,46 @@
 use anyhow::Result;
 use colored::Colorize;
-use crate::config::{get_models_dir, YUUKI_MODELS, HF_ORG};
+use crate::config::model_dir;
+use crate::hf::HfClient;
+use crate::registry::Registry;
 
 pub async fn execute(model: &str, variants: bool) -> Result<()> {
     println!("{}", "ℹ  Model Information".bright_cyan().bold());
     println!();
 
-    if !YUUKI_MODELS.contains(&model) {
-        anyhow::bail!("Model '{}' not found. Use 'yuy list models --remote' to see available models.", model);
-    }
+    let model_ref = Registry::load()?.resolve(model)?;
+    let entry = &model_ref.entry;
+    let model = entry.name.as_str();
 
     println!("{} {}", "Model:".bright_cyan().bold(), model.bright_yellow().bold());
     println!(
-        "{} https://huggingface.co/{}/{}",
+        "{} https://huggingface.co/{}",
         "URL:".bright_cyan(),
-        HF_ORG,
-        model
+        entry.source_repo
     );
+    if let Some(gguf_repo) = &entry.gguf_repo {
+        println!(
+            "{} https://huggingface.co/{}",
+            "GGUF:".bright_cyan(),
+            gguf_repo
+        );
+    }
+    if !entry.description.is_empty() {
+        println!("{} {}", "About:".bright_cyan(), entry.description);
+    }
+    if let Some(parameters) = &entry.parameters {
+        println!("{} {}", "Parameters:".bright_cyan(), parameters);
+    }
+    if let Some(modality) = &entry.modality {
+        println!("{} {}", "Modality:".bright_cyan(), modality);
+    }
+    if let Some(license) = &entry.license {
+        println!("{} {}", "License:".bright_cyan(), license);
+    }
     println!();
 
     // Check local status
-    let models_dir = get_models_dir()?;
-    let model_dir = models_dir.join(model);
+    let model_dir = model_dir(model)?;
 
     if model_dir.exists() {
         println!("{} {}", "Status:".bright_cyan(), "Downloaded ✓".bright_green());
@@ -35,19 +54,17 @@ pub async fn execute(model: &str, variants: bool) -> Result<()> {
         // List local variants
         println!("{}", "Local Variants:".bright_cyan());
         if let Ok(entries) = std::fs::read_dir(&model_dir) {
-            for entry in entries {
-                if let Ok(file) = entry {
-                    let filename = file.file_name();
-                    if filename.to_string_lossy().ends_with(".gguf") {
-                        let metadata = file.metadata()?;
-                        let size = crate::utils::format_size(metadata.len());
-                        println!(
-                            "  {} {} ({})",
-                            "•".bright_green(),
-                            filename.to_string_lossy().bright_white(),
-                            size.bright_black()
-                        );
-                    }
+            for file in entries.flatten() {
+                let filename = file.file_name();
+                if filename.to_string_lossy().ends_with(".gguf") {
+                    let metadata = file.metadata()?;
+                    let size = crate::utils::format_size(metadata.len());
+                    println!(
+                        "  {} {} ({})",
+                        "•".bright_green(),
+                        filename.to_string_lossy().bright_white(),
+                        size.bright_black()
+                    );
                 }
             }
         }
@@ -63,10 +80,35 @@ pub async fn execute(model: &str, variants: bool) -> Result<()> {
     if variants {
         println!();
         println!("{}", "Available Variants (on HuggingFace):".bright_cyan());
-        println!("  {} {} - Smallest size, good for mobile", "•".bright_green(), "q4_0".bright_white());
-        println!("  {} {} - Medium quality, balanced", "•".bright_green(), "q5_k_m".bright_white());
-        println!("  {} {} - High quality", "•".bright_green(), "q8_0".bright_white());
-        println!("  {} {} - Full precision (largest)", "•".bright_green(), "f32".bright_white());
+
+        let files = match HfClient::new().list_gguf_files(entry.download_repo()).await {
+            Ok(files) => files,
+            Err(e) => {
+                // Fall back to what the registry knows
+                println!("  {} Could not reach Hugging Face: {}", "⚠".bright_yellow(), e);
+                for quant in &entry.quants {
+                    println!(
+                        "  {} {} ({})",
+                        "•".bright_green(),
+                        quant.name.bright_white(),
+                        crate::utils::format_size(quant.size).bright_black()
+                    );
+                }
+                return Ok(());
+            }
+        };
+        if files.is_empty() {
+            println!("  {} No GGUF files published yet", "ℹ".bright_blue());
+        }
+        for file in &files {
+            println!(
+                "  {} {} - {} ({})",
+                "•".bright_green(),
+                file.quant.bright_white(),
+                file.filename,
+                crate::utils::format_size(file.size).bright_black()
+            );
+        }
     }
 
     Ok(())
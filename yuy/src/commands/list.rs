@@ -1,7 +1,9 @@
 use anyhow::Result;
 use colored::Colorize;
 use crate::cli::ListTarget;
-use crate::config::{get_models_dir, YUUKI_MODELS, HF_ORG};
+use crate::config::{local_models, model_roots, HF_ORG};
+use crate::hf::HfClient;
+use crate::registry::Registry;
 
 pub async fn execute(target: ListTarget) -> Result<()> {
     match target {
@@ -19,9 +21,10 @@ async fn list_local_models() -> Result<()> {
     println!("{}", "📋 Local Models".bright_cyan().bold());
     println!();
 
-    let models_dir = get_models_dir()?;
+    let roots = model_roots()?;
+    let models = local_models()?;
 
-    if !models_dir.exists() || std::fs::read_dir(&models_dir)?.next().is_none() {
+    if models.is_empty() {
         println!("{} No models downloaded yet.", "ℹ".bright_blue());
         println!();
         println!("{} Download a model:", "→".bright_blue());
@@ -29,41 +32,42 @@ async fn list_local_models() -> Result<()> {
         return Ok(());
     }
 
-    for entry in std::fs::read_dir(&models_dir)? {
-        let entry = entry?;
-        let model_name = entry.file_name();
-        let model_path = entry.path();
+    for (model_name, model_path) in models {
+        // Which root it's in only matters when there are several
+        let root = roots
+            .iter()
+            .find(|root| model_path.starts_with(root))
+            .filter(|_| roots.len() > 1)
+            .map(|root| format!("  ({})", root.display()))
+            .unwrap_or_default();
+        println!("{} {}{}", "•".bright_green(), model_name.bright_yellow().bold(), root.bright_black());
 
-        if model_path.is_dir() {
-            println!("{} {}", "•".bright_green(), model_name.to_string_lossy().bright_yellow().bold());
-
-            // List GGUF files in this model directory
-            if let Ok(files) = std::fs::read_dir(&model_path) {
-                for file_entry in files {
-                    if let Ok(file) = file_entry {
-                        let file_name = file.file_name();
-                        if file_name.to_string_lossy().ends_with(".gguf") {
-                            let metadata = file.metadata()?;
-                            let size = crate::utils::format_size(metadata.len());
-                            println!(
-                                "  {} {} ({})",
-                                "→".bright_blue(),
-                                file_name.to_string_lossy().bright_white(),
-                                size.bright_black()
-                            );
-                        }
-                    }
+        // List GGUF files in this model directory
+        if let Ok(files) = std::fs::read_dir(&model_path) {
+            for file in files.flatten() {
+                let file_name = file.file_name();
+                if file_name.to_string_lossy().ends_with(".gguf") {
+                    let metadata = file.metadata()?;
+                    let size = crate::utils::format_size(metadata.len());
+                    println!(
+                        "  {} {} ({})",
+                        "→".bright_blue(),
+                        file_name.to_string_lossy().bright_white(),
+                        size.bright_black()
+                    );
                 }
             }
-            println!();
         }
+        println!();
     }
 
-    println!(
-        "{} Location: {}",
-        "📁".bright_blue(),
-        models_dir.display().to_string().bright_black()
-    );
+    for root in &roots {
+        println!(
+            "{} Location: {}",
+            "📁".bright_blue(),
+            root.display().to_string().bright_black()
+        );
+    }
 
     Ok(())
 }
@@ -79,14 +83,40 @@ async fn list_remote_models() -> Result<()> {
     );
     println!();
 
-    for model in YUUKI_MODELS {
+    let hf = HfClient::new();
+
+    for entry in Registry::load()?.models() {
+        let model = entry.name.as_str();
         println!("{} {}", "•".bright_green(), model.bright_yellow().bold());
+        if !entry.description.is_empty() {
+            let details: Vec<&str> = [entry.parameters.as_deref(), entry.modality.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect();
+            if details.is_empty() {
+                println!("  {}", entry.description.bright_white());
+            } else {
+                println!("  {} ({})", entry.description.bright_white(), details.join(", ").bright_black());
+            }
+        }
         println!(
-            "  {} https://huggingface.co/{}/{}",
+            "  {} https://huggingface.co/{}",
             "🔗".bright_blue(),
-            HF_ORG,
-            model
+            entry.source_repo
         );
+
+        match hf.list_gguf_files(entry.download_repo()).await {
+            Ok(files) if !files.is_empty() => {
+                let quants: Vec<_> = files
+                    .iter()
+                    .map(|f| format!("{} ({})", f.quant, crate::utils::format_size(f.size)))
+                    .collect();
+                println!("  {} {}", "📦".bright_blue(), quants.join(", ").bright_white());
+            }
+            Ok(_) => println!("  {} {}", "📦".bright_blue(), "no GGUF files published yet".bright_black()),
+            Err(e) => println!("  {} {}", "⚠".bright_yellow(), e.to_string().bright_black()),
+        }
+
         println!(
             "  {} {}",
             "📥".bright_blue(),
@@ -95,11 +125,5 @@ async fn list_remote_models() -> Result<()> {
         println!();
     }
 
-    println!("{} Quantizations typically available:", "ℹ".bright_blue());
-    println!("  • {} (smallest, fastest)", "q4_0".bright_green());
-    println!("  • {} (balanced)", "q5_k_m".bright_green());
-    println!("  • {} (best quality)", "q8_0".bright_green());
-    println!("  • {} (full precision)", "f32".bright_green());
-
     Ok(())
 }
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use colored::Colorize;
+use crate::config::{get_config_path, load_base_config, save_config};
+use crate::hf::{endpoint, HfClient};
+
+pub async fn execute(token: Option<String>) -> Result<()> {
+    println!("{}", "🔑 Hugging Face Login".bright_cyan().bold());
+    println!();
+
+    let token = match token {
+        Some(t) => t,
+        None => {
+            println!(
+                "{} Create a token at {}",
+                "ℹ".bright_blue(),
+                "https://huggingface.co/settings/tokens".bright_blue()
+            );
+            print!("{} Paste your token: ", "?".bright_cyan());
+            std::io::Write::flush(&mut std::io::stdout())?;
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            input
+        }
+    };
+    let token = token.trim().to_string();
+
+    if token.is_empty() {
+        anyhow::bail!("No token given");
+    }
+
+    println!("{} Validating token...", "→".bright_blue());
+    let user = HfClient::with_endpoint(&endpoint(), Some(&token)).whoami().await?;
+
+    let mut config = load_base_config()?;
+    config.hf_token = Some(token);
+    save_config(&config)?;
+
+    println!();
+    println!(
+        "{} Logged in as {}",
+        "✓".bright_green().bold(),
+        user.bright_yellow().bold()
+    );
+    println!(
+        "{} Token saved to {}",
+        "→".bright_blue(),
+        get_config_path()?.display().to_string().bright_black()
+    );
+
+    if std::env::var("HF_TOKEN").is_ok() {
+        println!(
+            "{} HF_TOKEN is set and takes precedence over the saved token.",
+            "⚠".bright_yellow()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anyhow::Result;
+use colored::Colorize;
+use crate::config::{load_base_config, save_config};
+
+pub async fn execute() -> Result<()> {
+    let mut config = load_base_config()?;
+
+    if config.hf_token.take().is_none() {
+        println!("{} Not logged in.", "ℹ".bright_blue());
+    } else {
+        save_config(&config)?;
+        println!("{} Hugging Face token removed.", "✓".bright_green());
+    }
+
+    if std::env::var("HF_TOKEN").is_ok() {
+        println!(
+            "{} HF_TOKEN is still set in your environment and will keep being used.",
+            "⚠".bright_yellow()
+        );
+    }
+
+    Ok(())
+}
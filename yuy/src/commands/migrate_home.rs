@@ -0,0 +1,147 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::fs;
+use std::path::PathBuf;
+use crate::config::{Layout, HOME_ENV};
+use crate::utils::move_path;
+
+/// Entries that belong in the config and cache directories. Everything
+/// else, models and sessions included, is data.
+const CONFIG_ENTRIES: &[&str] = &["config.toml", "registry.toml", "presets", "templates"];
+const CACHE_ENTRIES: &[&str] = &["registry-cache.toml"];
+
+pub async fn execute(to: Option<PathBuf>, xdg: bool, dry_run: bool) -> Result<()> {
+    println!("{}", "📦 Migrate Home".bright_cyan().bold());
+    println!();
+
+    let from = Layout::current()?.value;
+    let target = match to {
+        Some(dir) => Layout::single(std::path::absolute(&dir)?),
+        None => Layout::xdg()?,
+    };
+
+    let moves = plan(&from, &target)?;
+    if moves.is_empty() {
+        println!("{} Nothing to move", "ℹ".bright_blue());
+        return Ok(());
+    }
+    for (src, dst) in &moves {
+        println!(
+            "  {} {} {}",
+            src.display().to_string().bright_white(),
+            "→".bright_blue(),
+            dst.display().to_string().bright_yellow()
+        );
+    }
+    println!();
+    if dry_run {
+        println!("{} Dry run: nothing was moved", "ℹ".bright_blue());
+        return Ok(());
+    }
+
+    for (src, dst) in &moves {
+        move_path(src, dst)
+            .with_context(|| format!("Failed to move {} to {}", src.display(), dst.display()))?;
+    }
+    // Only succeeds once empty, which is the point
+    for dir in [&from.config, &from.data, &from.cache] {
+        let _ = fs::remove_dir(dir);
+    }
+    println!("{} Moved {} item(s)", "✓".bright_green().bold(), moves.len());
+
+    if std::env::var_os(HOME_ENV).is_some() {
+        println!(
+            "{} {} is set and still points at the old home. Update or unset it.",
+            "⚠".bright_yellow(),
+            HOME_ENV
+        );
+    } else if !xdg && target != Layout::legacy()? {
+        let home = target.config.display().to_string();
+        println!(
+            "{} Point yuy at it with: {} (or {})",
+            "→".bright_blue(),
+            format!("export {}={}", HOME_ENV, home).bright_green(),
+            format!("--home {}", home).bright_green()
+        );
+    }
+    Ok(())
+}
+
+/// `(from, to)` for every entry of `from`'s directories that lives
+/// elsewhere in `to`. Fails, before anything moves, when a destination
+/// already exists.
+fn plan(from: &Layout, to: &Layout) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut dirs = vec![&from.config, &from.data, &from.cache];
+    dirs.sort();
+    dirs.dedup();
+
+    let mut moves = Vec::new();
+    for dir in dirs {
+        let Ok(entries) = fs::read_dir(dir) else {
+            continue;
+        };
+        let mut names: Vec<_> = entries.flatten().map(|e| e.file_name()).collect();
+        names.sort();
+        for name in names {
+            let key = name.to_string_lossy();
+            let dest = if CONFIG_ENTRIES.contains(&key.as_ref()) {
+                &to.config
+            } else if CACHE_ENTRIES.contains(&key.as_ref()) {
+                &to.cache
+            } else {
+                &to.data
+            };
+            let (src, dst) = (dir.join(&name), dest.join(&name));
+            if src == dst {
+                continue;
+            }
+            if dst.starts_with(&src) {
+                anyhow::bail!("Can't move {} into itself", src.display());
+            }
+            if dst.exists() {
+                anyhow::bail!("{} already exists. Move or remove it first; nothing was moved.", dst.display());
+            }
+            moves.push((src, dst));
+        }
+    }
+    Ok(moves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_a_home_into_config_data_and_cache() {
+        let root = tempfile::tempdir().unwrap();
+        let home = root.path().join("home");
+        fs::create_dir_all(home.join("models/Yuuki-best")).unwrap();
+        fs::create_dir_all(home.join("presets")).unwrap();
+        fs::write(home.join("models/Yuuki-best/yuuki-best-q4_0.gguf"), b"gguf").unwrap();
+        fs::write(home.join("config.toml"), "").unwrap();
+        fs::write(home.join("registry-cache.toml"), "").unwrap();
+
+        let from = Layout::single(&home);
+        let to = Layout {
+            config: root.path().join("config"),
+            data: root.path().join("data"),
+            cache: root.path().join("cache"),
+        };
+        let moves = plan(&from, &to).unwrap();
+        assert_eq!(moves.len(), 4);
+        for (src, dst) in &moves {
+            move_path(src, dst).unwrap();
+        }
+        assert!(to.config.join("config.toml").exists());
+        assert!(to.config.join("presets").is_dir());
+        assert!(to.cache.join("registry-cache.toml").exists());
+        assert!(to.data.join("models/Yuuki-best/yuuki-best-q4_0.gguf").exists());
+        assert_eq!(fs::read_dir(&home).unwrap().count(), 0);
+
+        // Back again, but the destination is taken
+        fs::write(home.join("config.toml"), "").unwrap();
+        let err = plan(&to, &from).unwrap_err().to_string();
+        assert!(err.contains("already exists"), "{}", err);
+        assert!(plan(&to, &to).unwrap().is_empty());
+    }
+}
@@ -1,8 +1,20 @@
 pub mod download;
 pub mod run;
+pub mod batch;
 pub mod list;
 pub mod info;
 pub mod remove;
+pub mod models;
 pub mod runtime;
+pub mod ollama;
 pub mod doctor;
+pub mod migrate_home;
 pub mod setup;
+pub mod login;
+pub mod logout;
+pub mod registry;
+pub mod config;
+pub mod verify;
+pub mod sessions;
+pub mod template;
+pub mod preset;
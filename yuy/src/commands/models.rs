@@ -0,0 +1,132 @@
+use anyhow::Result;
+use colored::Colorize;
+use indicatif::ProgressBar;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use crate::cli::ModelsAction;
+use crate::config::{local_models, model_dir, model_roots, prune_org_dir};
+use crate::registry::Registry;
+use crate::utils::{dir_size, format_size, free_disk_space, move_path, same_filesystem};
+
+pub async fn execute(action: ModelsAction) -> Result<()> {
+    match action {
+        ModelsAction::Move { model, to } => move_model(&model, &to).await,
+    }
+}
+
+async fn move_model(model: &str, to: &Path) -> Result<()> {
+    println!("{}", "📦 Move Model".bright_cyan().bold());
+    println!();
+
+    let roots = model_roots()?;
+    let Some(root) = find_root(&roots, to) else {
+        let roots: Vec<_> = roots.iter().map(|r| r.display().to_string()).collect();
+        let to = std::path::absolute(to)?;
+        anyhow::bail!(
+            "{} is not a storage root ({}). Add it to model_roots first: yuy config set model_roots {},{}",
+            to.display(),
+            roots.join(", "),
+            roots.join(","),
+            to.display()
+        );
+    };
+
+    // Models dropped from the registry can still be moved by name
+    let name = match Registry::load()?.resolve(model) {
+        Ok(model_ref) => model_ref.entry.name,
+        Err(e) => match local_models()?.into_iter().find(|(n, _)| n.eq_ignore_ascii_case(model)) {
+            Some((name, _)) => name,
+            None => return Err(e),
+        },
+    };
+    let from = model_dir(&name)?;
+    if !from.is_dir() {
+        anyhow::bail!("Model '{}' is not downloaded.", name);
+    }
+    let dest = root.join(&name);
+    if resolve(&from) == resolve(&dest) {
+        println!("{} {} is already in {}", "ℹ".bright_blue(), name.bright_yellow(), root.display());
+        return Ok(());
+    }
+    if dest.exists() {
+        anyhow::bail!("{} already exists. Remove it first or pick another root.", dest.display());
+    }
+
+    // Only a copy to another filesystem needs room for the files
+    let size = dir_size(&from);
+    let needed = if same_filesystem(&from, &root) { 0 } else { size };
+    if let Some(free) = free_disk_space(&root).filter(|&free| free < needed) {
+        anyhow::bail!(
+            "Not enough space in {}: {} needs {}, {} free",
+            root.display(),
+            name,
+            format_size(size),
+            format_size(free)
+        );
+    }
+
+    println!("{} From: {}", "→".bright_blue(), from.display().to_string().bright_white());
+    println!("{} To:   {}", "→".bright_blue(), dest.display().to_string().bright_white());
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    spinner.set_message(format!("Moving {} ({})...", name, format_size(size)));
+    let (src, dst) = (from.clone(), dest.clone());
+    tokio::task::spawn_blocking(move || move_path(&src, &dst)).await??;
+    spinner.finish_and_clear();
+    prune_org_dir(&name, &from)?;
+
+    println!();
+    println!(
+        "{} Moved {} ({}) to {}",
+        "✓".bright_green(),
+        name.bright_yellow(),
+        format_size(size),
+        root.display().to_string().bright_white()
+    );
+    Ok(())
+}
+
+/// The root in `roots` that `to` names, however it is spelled: relative,
+/// through a symlink, or with `..` in it.
+fn find_root(roots: &[PathBuf], to: &Path) -> Option<PathBuf> {
+    let to = resolve(to);
+    roots.iter().find(|root| resolve(root) == to).cloned()
+}
+
+/// `path` with symlinks and `..` resolved when it exists, else absolute.
+fn resolve(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path)
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_roots_however_they_are_spelled() {
+        let dir = tempfile::tempdir().unwrap();
+        let (fast, slow) = (dir.path().join("fast"), dir.path().join("slow"));
+        std::fs::create_dir_all(&fast).unwrap();
+        std::fs::create_dir_all(&slow).unwrap();
+        let roots = vec![fast.clone(), slow.clone(), dir.path().join("not-yet")];
+
+        assert_eq!(find_root(&roots, &slow), Some(slow.clone()));
+        assert_eq!(find_root(&roots, &fast.join("../slow/")), Some(slow.clone()));
+        assert_eq!(find_root(&roots, &dir.path().join("not-yet")), Some(roots[2].clone()));
+        assert_eq!(find_root(&roots, dir.path()), None);
+        assert_eq!(find_root(&roots, &dir.path().join("other")), None);
+
+        #[cfg(unix)]
+        {
+            let link = dir.path().join("link");
+            std::os::unix::fs::symlink(&fast, &link).unwrap();
+            assert_eq!(find_root(&roots, &link), Some(fast.clone()));
+            // A root configured through the symlink matches its target too
+            let through_link = vec![link.clone()];
+            assert_eq!(find_root(&through_link, &fast), Some(link));
+        }
+    }
+}
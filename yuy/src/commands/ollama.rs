@@ -0,0 +1,162 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::time::Duration;
+use crate::backend::{ChatFormat, RuntimeOptions};
+use crate::cli::OllamaAction;
+use crate::commands::download::sha256_file;
+use crate::commands::run::{local_variants, render_template, runtime_options};
+use crate::config::model_dir;
+use crate::ollama::{Modelfile, OllamaClient};
+use crate::preset::PresetStore;
+use crate::registry::Registry;
+
+pub async fn execute(action: OllamaAction) -> Result<()> {
+    match action {
+        OllamaAction::Import { model, quant, preset, template, vars, name, modelfile_only } => {
+            import(&model, quant, preset, template, &vars, name, modelfile_only).await
+        }
+    }
+}
+
+async fn import(
+    model: &str,
+    quant: Option<String>,
+    preset: Option<String>,
+    template: Option<String>,
+    vars: &[String],
+    name: Option<String>,
+    modelfile_only: bool,
+) -> Result<()> {
+    println!("{}", "🦙 Ollama Import".bright_cyan().bold());
+    println!();
+
+    let model_ref = Registry::load()?.resolve(model)?;
+    let entry = &model_ref.entry;
+    let quant = model_ref.quant_or(quant)?;
+    let model_dir = model_dir(&entry.name)?;
+    let local = local_variants(&model_dir);
+    if local.is_empty() {
+        anyhow::bail!(
+            "Model '{}' is not downloaded yet. Download it first: yuy download {}",
+            entry.name,
+            entry.name
+        );
+    }
+
+    // Without --quant, the only downloaded file is unambiguous
+    let (filename, quant) = match quant {
+        Some(quant) => local
+            .iter()
+            .find(|(_, q)| q.eq_ignore_ascii_case(&quant))
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "No '{}' file downloaded for {}. Try: yuy download {} --quant {}",
+                    quant, entry.name, entry.name, quant
+                )
+            })?,
+        None if local.len() == 1 => local[0].clone(),
+        None => {
+            let quants: Vec<_> = local.iter().map(|(_, q)| q.as_str()).collect();
+            anyhow::bail!(
+                "{} has several downloads ({}). Pick one with --quant",
+                entry.name,
+                quants.join(", ")
+            );
+        }
+    };
+
+    let (format, system) = match template {
+        Some(name) => {
+            let (template, system) = render_template(&name, vars)?;
+            (template.format, Some(system))
+        }
+        None if !vars.is_empty() => {
+            anyhow::bail!("--var needs a template. Pick one with --template (see: yuy template list)")
+        }
+        None => (ChatFormat::default(), None),
+    };
+    // The model's config defaults are baked in, as `yuy run` would apply them
+    let mut preset = PresetStore::open()?.find(preset.as_deref())?;
+    let options = runtime_options(&entry.name, &RuntimeOptions::default(), &mut preset)?;
+    let modelfile = Modelfile { gguf: model_dir.join(&filename), format, system, preset, options };
+    // The name `yuy run --runtime ollama` looks for, so it won't pull
+    let name = name.unwrap_or_else(|| entry.ollama_model(&quant));
+
+    let path = model_dir.join(format!("Modelfile.{}", quant));
+    std::fs::write(&path, modelfile.render()?)?;
+    println!("{} File: {}", "→".bright_blue(), filename.bright_yellow());
+    println!(
+        "{} Wrote {}",
+        "✓".bright_green(),
+        path.display().to_string().bright_white()
+    );
+
+    if modelfile_only {
+        println!();
+        println!(
+            "{} Register it with: {}",
+            "→".bright_blue(),
+            format!("ollama create {} -f {}", name, path.display()).bright_green()
+        );
+        return Ok(());
+    }
+
+    let client = OllamaClient::new();
+    if let Err(e) = client.version().await {
+        anyhow::bail!(
+            "{}. Start it with `ollama serve`, or register the Modelfile later with: ollama create {} -f {}",
+            e,
+            name,
+            path.display()
+        );
+    }
+    println!("{} Server: {}", "→".bright_blue(), client.host().bright_white());
+    println!();
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    spinner.set_message(format!("Hashing {}...", filename));
+    let gguf = modelfile.gguf.clone();
+    let digest = tokio::task::spawn_blocking(move || sha256_file(&gguf)).await??;
+    spinner.finish_and_clear();
+
+    if client.has_blob(&digest).await? {
+        println!("{} Ollama already has this file", "ℹ".bright_blue());
+    } else {
+        let size = std::fs::metadata(&modelfile.gguf)?.len();
+        let pb = ProgressBar::new(size);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")?
+                .progress_chars("#>-"),
+        );
+        let bar = pb.clone();
+        client
+            .push_blob(&modelfile.gguf, &digest, move |n| bar.inc(n))
+            .await?;
+        pb.finish_and_clear();
+        println!("{} Copied {} to Ollama", "✓".bright_green(), filename.bright_yellow());
+    }
+
+    let spinner = ProgressBar::new_spinner();
+    spinner.enable_steady_tick(Duration::from_millis(120));
+    client
+        .create(&name, &modelfile, &digest, |status| spinner.set_message(status.to_string()))
+        .await?;
+    spinner.finish_and_clear();
+
+    println!("{} Created Ollama model {}", "✓".bright_green(), name.bright_yellow());
+    println!();
+    if name == entry.ollama_model(&quant) {
+        println!(
+            "{} Run it with: {}",
+            "→".bright_blue(),
+            format!("yuy run {} --quant {} --runtime ollama", entry.name, quant).bright_green()
+        );
+    } else {
+        println!("{} Run it with: {}", "→".bright_blue(), format!("ollama run {}", name).bright_green());
+    }
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use anyhow::Result;
+use colored::Colorize;
+use crate::cli::PresetAction;
+use crate::preset::{load_file, Preset, PresetStore, DEFAULT_PRESET};
+use crate::utils::open_in_editor;
+
+pub async fn execute(action: PresetAction) -> Result<()> {
+    let store = PresetStore::open()?;
+    match action {
+        PresetAction::List => list(&store),
+        PresetAction::Show { name } => show(&store, &name),
+        PresetAction::New { name, from } => new(&store, &name, from),
+        PresetAction::Edit { name } => edit(&store, &name),
+        PresetAction::Delete { name } => delete(&store, &name),
+    }
+}
+
+fn list(store: &PresetStore) -> Result<()> {
+    println!("{}", "🎛  Generation Presets".bright_cyan().bold());
+    println!();
+
+    for (preset, source) in store.list()? {
+        let default = if preset.name == DEFAULT_PRESET { ", default" } else { "" };
+        println!(
+            "{} {}  {}",
+            "•".bright_green(),
+            preset.name.bright_yellow().bold(),
+            format!("({}{})", source, default).bright_black()
+        );
+        if !preset.description.is_empty() {
+            println!("  {}", preset.description.bright_white());
+        }
+        let params: Vec<String> = preset
+            .parameters()
+            .into_iter()
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect();
+        if !params.is_empty() {
+            println!("  {}", params.join(" ").bright_black());
+        }
+    }
+
+    println!();
+    println!(
+        "{} Use one: {}",
+        "→".bright_blue(),
+        "yuy run <model> --preset creative".bright_green()
+    );
+    Ok(())
+}
+
+fn show(store: &PresetStore, name: &str) -> Result<()> {
+    let preset = store.find(Some(name))?;
+
+    println!("{} {}", "Preset:".bright_cyan().bold(), preset.name.bright_yellow().bold());
+    if !preset.description.is_empty() {
+        println!("{} {}", "About:".bright_cyan(), preset.description);
+    }
+    let path = store.path(&preset.name)?;
+    if path.exists() {
+        println!("{} {}", "File:".bright_cyan(), path.display().to_string().bright_black());
+    }
+    println!();
+
+    let params = preset.parameters();
+    if params.is_empty() {
+        println!("{} Uses the runtime's defaults for everything", "ℹ".bright_blue());
+    }
+    for (name, value) in params {
+        println!("  {} {}", format!("{}:", name).bright_white(), value.bright_green());
+    }
+    Ok(())
+}
+
+fn new(store: &PresetStore, name: &str, from: Option<String>) -> Result<()> {
+    let path = store.path(name)?;
+    if path.exists() {
+        anyhow::bail!("Preset '{}' already exists. Edit it with: yuy preset edit {}", name, name);
+    }
+
+    let mut preset = match from {
+        Some(base) => store.find(Some(&base))?,
+        None => store.find(Some(DEFAULT_PRESET))?,
+    };
+    preset.name = name.to_string();
+    let path = store.save(&preset)?;
+
+    println!(
+        "{} Created preset {} at {}",
+        "✓".bright_green(),
+        name.bright_yellow(),
+        path.display().to_string().bright_white()
+    );
+    println!(
+        "{} Edit it with: {}",
+        "→".bright_blue(),
+        format!("yuy preset edit {}", name).bright_green()
+    );
+    Ok(())
+}
+
+fn edit(store: &PresetStore, name: &str) -> Result<()> {
+    let mut path = store.path(name)?;
+    if !path.exists() {
+        // Editing a built-in or config preset creates a file that replaces it
+        let preset = store.find(Some(name))?;
+        path = store.save(&preset)?;
+        println!(
+            "{} Copied preset {} to {}",
+            "ℹ".bright_blue(),
+            preset.name.bright_yellow(),
+            path.display().to_string().bright_white()
+        );
+    }
+
+    open_in_editor(&path)?;
+
+    // Catch mistakes now rather than at the next `yuy run`
+    let preset: Preset = load_file(&path)?;
+    preset.validate()?;
+    println!(
+        "{} Saved preset {} ({} parameters)",
+        "✓".bright_green(),
+        preset.name.bright_yellow(),
+        preset.parameters().len()
+    );
+    Ok(())
+}
+
+fn delete(store: &PresetStore, name: &str) -> Result<()> {
+    let path = store.path(name)?;
+    if !path.exists() {
+        anyhow::bail!(
+            "No preset file for '{}'. Built-in presets can't be deleted; config presets live in config.toml.",
+            name
+        );
+    }
+    std::fs::remove_file(&path)?;
+    println!("{} Deleted preset {}", "✓".bright_green(), name.bright_yellow());
+    Ok(())
+}
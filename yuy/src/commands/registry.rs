@@ -0,0 +1,85 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::time::Duration;
+use crate::cli::RegistryAction;
+use crate::config::load_config;
+use crate::registry::{get_cached_registry_path, get_user_registry_path, validate, Registry, DEFAULT_REGISTRY_URL};
+
+/// How long `registry update` waits for the registry file.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub async fn execute(action: RegistryAction) -> Result<()> {
+    match action {
+        RegistryAction::Update => update_registry().await,
+        RegistryAction::Show => show_registry(),
+    }
+}
+
+async fn update_registry() -> Result<()> {
+    println!("{}", "🔄 Registry Update".bright_cyan().bold());
+    println!();
+
+    let url = load_config()?
+        .registry_url
+        .unwrap_or_else(|| DEFAULT_REGISTRY_URL.to_string());
+    println!("{} Fetching {}", "→".bright_blue(), url.bright_black());
+
+    let client = reqwest::Client::builder().timeout(FETCH_TIMEOUT).build()?;
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .with_context(|| format!("Failed to fetch registry from {}", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to fetch registry: HTTP {}", response.status());
+    }
+    let content = response.text().await?;
+
+    // Never replace a working cache with something unparseable
+    let count = validate(&content).context("The fetched registry is invalid")?;
+
+    let path = get_cached_registry_path()?;
+    let tmp = path.with_extension("toml.tmp");
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(&tmp, &content)?;
+    std::fs::rename(&tmp, &path)?;
+
+    println!(
+        "{} Registry updated: {} model(s)",
+        "✓".bright_green().bold(),
+        count
+    );
+    println!(
+        "  {} {}",
+        "Saved to:".bright_cyan(),
+        path.display().to_string().bright_yellow()
+    );
+
+    Ok(())
+}
+
+fn show_registry() -> Result<()> {
+    println!("{}", "📚 Model Registry".bright_cyan().bold());
+    println!();
+
+    println!("{}", "Sources (later ones take precedence):".bright_cyan());
+    println!("  {} built-in", "•".bright_green());
+    for path in [get_cached_registry_path()?, get_user_registry_path()?] {
+        let status = if path.exists() { "✓".bright_green() } else { "-".bright_black() };
+        println!("  {} {}", status, path.display().to_string().bright_yellow());
+    }
+    println!();
+
+    for entry in Registry::load()?.models() {
+        let quants: Vec<_> = entry.quants.iter().map(|q| q.name.as_str()).collect();
+        println!("{} {}", "•".bright_green(), entry.name.bright_yellow().bold());
+        println!("  {} {}", "Repo:".bright_white(), entry.download_repo());
+        if !quants.is_empty() {
+            println!("  {} {}", "Quants:".bright_white(), quants.join(", "));
+        }
+    }
+
+    Ok(())
+}
@@ -1,15 +1,24 @@
 use anyhow::Result;
 use colored::Colorize;
-use crate::config::get_models_dir;
+use std::path::PathBuf;
+use crate::config::{model_roots, prune_org_dir};
+use crate::registry::Registry;
 
 pub async fn execute(model: &str) -> Result<()> {
     println!("{}", "🗑️  Remove Model".bright_cyan().bold());
     println!();
 
-    let models_dir = get_models_dir()?;
-    let model_dir = models_dir.join(model);
+    // Models dropped from the registry can still be removed by directory name
+    let model = match Registry::load()?.resolve(model) {
+        Ok(model_ref) => model_ref.entry.name,
+        Err(_) if is_plain_dir_name(model) => model.to_string(),
+        Err(e) => return Err(e),
+    };
+    let model = model.as_str();
 
-    if !model_dir.exists() {
+    let model_dirs = copies(&model_roots()?, model);
+
+    if model_dirs.is_empty() {
         println!(
             "{} Model '{}' is not downloaded.",
             "ℹ".bright_blue(),
@@ -18,21 +27,17 @@ pub async fn execute(model: &str) -> Result<()> {
         return Ok(());
     }
 
-    // Calculate total size
-    let mut total_size = 0u64;
-    for entry in std::fs::read_dir(&model_dir)? {
-        if let Ok(file) = entry {
-            if let Ok(metadata) = file.metadata() {
-                total_size += metadata.len();
-            }
-        }
-    }
+    let total_size: u64 = model_dirs.iter().map(|dir| crate::utils::dir_size(dir)).sum();
 
     println!(
         "{} About to remove: {}",
         "⚠".bright_yellow(),
         model.bright_yellow().bold()
     );
+    // Every root's copy goes, or the model would still be found
+    for dir in &model_dirs {
+        println!("  {} {}", "•".bright_black(), dir.display().to_string().bright_white());
+    }
     println!(
         "{} Space to free: {}",
         "→".bright_blue(),
@@ -51,7 +56,10 @@ pub async fn execute(model: &str) -> Result<()> {
         return Ok(());
     }
 
-    std::fs::remove_dir_all(&model_dir)?;
+    for dir in &model_dirs {
+        std::fs::remove_dir_all(dir)?;
+        prune_org_dir(model, dir)?;
+    }
 
     println!();
     println!(
@@ -67,3 +75,30 @@ pub async fn execute(model: &str) -> Result<()> {
 
     Ok(())
 }
+
+/// `model`'s directory in each of `roots` that has one.
+fn copies(roots: &[PathBuf], model: &str) -> Vec<PathBuf> {
+    roots.iter().map(|root| root.join(model)).filter(|dir| dir.is_dir()).collect()
+}
+
+fn is_plain_dir_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':'])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_model_in_every_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots: Vec<_> = ["nvme", "archive", "models"].iter().map(|r| dir.path().join(r)).collect();
+        std::fs::create_dir_all(roots[0].join("Yuuki-best")).unwrap();
+        std::fs::create_dir_all(roots[2].join("Yuuki-best")).unwrap();
+        std::fs::create_dir_all(roots[1].join("org/repo")).unwrap();
+
+        assert_eq!(copies(&roots, "Yuuki-best"), [roots[0].join("Yuuki-best"), roots[2].join("Yuuki-best")]);
+        assert_eq!(copies(&roots, "org/repo"), [roots[1].join("org/repo")]);
+        assert!(copies(&roots, "Yuuki-3.7").is_empty());
+    }
+}
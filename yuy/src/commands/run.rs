@@ -1,200 +1,817 @@
-use anyhow::{Context, Result};
+use anyhow::Result;
 use colored::Colorize;
-use std::process::Command;
-use crate::config::{get_models_dir, YUUKI_MODELS, OLLAMA_ORG, HF_ORG};
-use crate::utils::command_exists;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{IsTerminal, Read, Write};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::backend::{Backend, ChatFormat, Interrupted, ModelSource, NoRuntime, RuntimeOptions, RuntimeUnavailable};
+use crate::config::{load_config, model_dir};
+use crate::hf::parse_quant;
+use crate::ollama::OllamaClient;
+use crate::preset::{Preset, PresetStore};
+use crate::registry::{ModelEntry, Registry};
+use crate::settings::{print_explain, Setting, Settings, Source, DEFAULT_QUANT};
+use crate::session::{generate_name, Message, Role, Session, SessionStore};
+use crate::server::{LlamaServer, DEFAULT_READY_TIMEOUT};
+use crate::template::{parse_var, Template, TemplateStore};
+use crate::utils::{command_exists, detect_platform, recommend_quant, SystemInfo, Variant, DEFAULT_CONTEXT};
 
-pub async fn execute(
-    model: &str,
-    runtime: Option<String>,
-    quant: Option<String>,
-    preset: Option<String>,
-    _resume: bool,
-    _template: Option<String>,
-) -> Result<()> {
-    println!("{}", "🚀 Yuuki Runtime".bright_cyan().bold());
-    println!();
+/// Flags of `yuy run`.
+#[derive(Debug, Default)]
+pub struct RunOptions {
+    pub runtime: Option<String>,
+    pub quant: Option<String>,
+    pub preset: Option<String>,
+    pub resume: bool,
+    pub session: Option<String>,
+    pub template: Option<String>,
+    pub vars: Vec<String>,
+    /// One-shot prompt; stdin is only read without it
+    pub prompt: Option<String>,
+    /// `--ctx`, `--threads`, `--gpu-layers` and arguments after `--`
+    pub load: RuntimeOptions,
+    /// Print the effective settings and where they came from, then exit
+    pub explain: bool,
+}
 
-    // Validate model
-    if !YUUKI_MODELS.contains(&model) {
-        anyhow::bail!("Model '{}' not found. Use 'yuy list models' to see available models.", model);
+pub async fn execute(model: Option<String>, options: RunOptions) -> Result<()> {
+    let RunOptions { runtime, quant, preset, resume, session, template, vars, prompt, load, explain } = options;
+    let settings = Settings::load()?;
+    let model_setting = settings.model(model);
+
+    // A prompt or piped input means one-shot mode: only the reply goes to stdout
+    let prompt = if explain {
+        None
+    } else {
+        let stdin = std::io::stdin();
+        let is_terminal = stdin.is_terminal();
+        read_prompt(prompt, stdin.lock(), is_terminal)?
+    };
+    let quiet = prompt.is_some() || explain;
+    let persist = prompt.is_none() || resume || session.is_some();
+
+    if !quiet {
+        println!("{}", "🚀 Yuuki Runtime".bright_cyan().bold());
+        println!();
     }
 
-    // Check if model is downloaded
-    let models_dir = get_models_dir()?;
-    let model_dir = models_dir.join(model);
-    
-    if !model_dir.exists() {
-        println!(
-            "{} Model '{}' is not downloaded yet.",
-            "✗".bright_red(),
-            model.bright_yellow()
-        );
-        println!(
-            "\n{} Download it first: {}",
-            "→".bright_blue(),
-            format!("yuy download {}", model).bright_green()
-        );
+    // Validate model
+    let model_ref = Registry::load()?.resolve(&model_setting.value)?;
+    let entry = &model_ref.entry;
+    let model = entry.name.as_str();
+    let quant = model_ref.quant_setting(quant)?;
+
+    let store = SessionStore::open()?;
+    let mut session = open_session(&store, model, resume, session, quiet)?;
+
+    // Flags win over what the session was started with
+    let from_session = |value: &Option<String>| {
+        let value = value.clone()?;
+        Some(Setting::new(value, Source::Session(session.name.clone())))
+    };
+    let quant = match settings.quant(quant.or_else(|| from_session(&session.quant))) {
+        Some(quant) => quant,
+        None => auto_quant(entry)?,
+    };
+    let session_runtime = session.runtime.as_deref().map(|r| (session.name.as_str(), r));
+    let chain = settings.runtimes(runtime, session_runtime)?;
+    let preset = preset
+        .map(|p| Setting::new(p, Source::Flag("--preset")))
+        .or_else(|| from_session(&session.preset));
+    // Fail on an unknown preset before anything starts
+    let mut sampling = PresetStore::open()?.find(preset.as_ref().map(|p| p.value.as_str()))?;
+    let preset_ctx = sampling.ctx_size;
+    let flags = load;
+    let load = runtime_options(model, &flags, &mut sampling)?;
+
+    if explain {
+        let preset = preset.unwrap_or_else(|| Setting::new(sampling.name.clone(), Source::Default));
+        let template = match template {
+            Some(name) => Some(Setting::new(name, Source::Flag("--template"))),
+            None => from_session(&session.template),
+        };
+        let model_key = |key: &str| settings.config_source(&format!("models.{}.{}", model, key));
+        let ctx_source = if flags.ctx_size.is_some() {
+            Source::Flag("--ctx")
+        } else if preset_ctx.is_some() {
+            Source::Preset(sampling.name.clone())
+        } else if load.ctx_size.is_some() {
+            model_key("ctx_size")
+        } else {
+            Source::Default
+        };
+
+        let mut rows: Vec<_> = settings.profile().into_iter().collect();
+        rows.extend([
+            ("model", model_setting),
+            ("quant", quant),
+            ("runtime", Setting::new(chain.value.join(", "), chain.source)),
+            ("preset", preset),
+        ]);
+        rows.extend(template.map(|t| ("template", t)));
+        rows.push(("ctx_size", Setting::new(load.ctx_size.unwrap_or(DEFAULT_CONTEXT).to_string(), ctx_source)));
+        let flag_or_model = |set_by_flag: bool, flag: &'static str, key: &str| {
+            if set_by_flag { Source::Flag(flag) } else { model_key(key) }
+        };
+        if let Some(threads) = load.threads {
+            let source = flag_or_model(flags.threads.is_some(), "--threads", "threads");
+            rows.push(("threads", Setting::new(threads.to_string(), source)));
+        }
+        if let Some(layers) = load.gpu_layers {
+            let source = flag_or_model(flags.gpu_layers.is_some(), "--gpu-layers", "gpu_layers");
+            rows.push(("gpu_layers", Setting::new(layers.to_string(), source)));
+        }
+        // Configured arguments come first, then the ones after `--`
+        let (configured, passed) = load.args.split_at(load.args.len() - flags.args.len());
+        if !configured.is_empty() {
+            rows.push(("args", Setting::new(configured.join(" "), model_key("args"))));
+        }
+        if !passed.is_empty() {
+            rows.push(("args", Setting::new(passed.join(" "), Source::Flag("--"))));
+        }
+        print_explain(&rows);
         return Ok(());
     }
 
-    // Find GGUF file
-    let quant_str = quant.unwrap_or_else(|| "q5_k_m".to_string());
-    let filename = format!("{}-{}.gguf", model.to_lowercase(), quant_str);
-    let model_path = model_dir.join(&filename);
+    let format = apply_template(&mut session, template, &vars)?;
 
-    if !model_path.exists() {
-        anyhow::bail!(
-            "Model file '{}' not found. Available quantizations may differ. Try: yuy info {}",
-            filename, model
-        );
+    let (runtime_name, backend) = pick_backend(entry, &chain.value, &quant.value, format, &sampling, &load, 1).await?;
+
+    session.quant = Some(quant.value);
+    session.runtime = Some(runtime_name);
+    session.preset = preset.map(|p| p.value);
+    let preset = session.preset.clone();
+
+    if let Some(prompt) = prompt {
+        return one_shot(&store, &mut session, &backend, &sampling, prompt, persist, &mut std::io::stdout()).await;
     }
 
+    match &backend {
+        Backend::LlamaCpp { model: source, .. } => print_source(source),
+        Backend::LlamaServer(server) => {
+            print_source(server.source());
+            println!("{} Server: {}", "→".bright_blue(), server.url().bright_white());
+        }
+        Backend::Ollama { client, model: ollama_model, .. } => {
+            println!(
+                "{} Using Ollama model {}",
+                "▶".bright_green(),
+                ollama_model.bright_yellow()
+            );
+            println!("{} Server: {}", "→".bright_blue(), client.host().bright_white());
+        }
+    }
     println!("{} Model: {}", "→".bright_blue(), model.bright_green());
-    println!("{} File: {}", "→".bright_blue(), filename.bright_yellow());
     if let Some(p) = &preset {
         println!("{} Preset: {}", "→".bright_blue(), p.bright_magenta());
     }
+    if let Some(t) = &session.template {
+        println!("{} Template: {}", "→".bright_blue(), t.bright_magenta());
+    }
+    let params = load.parameters();
+    if !params.is_empty() {
+        let params: Vec<_> = params.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+        println!("{} Runtime: {}", "→".bright_blue(), params.join(" ").bright_white());
+    }
+    println!("{} Session: {}", "→".bright_blue(), session.name.bright_yellow());
     println!();
 
-    // Determine runtime
-    let runtime_name = runtime.unwrap_or_else(|| "llama-cpp".to_string());
+    chat_loop(&store, &mut session, &backend, &sampling).await
+}
 
-    match runtime_name.as_str() {
-        "llama-cpp" => run_with_llama_cpp(&model_path, preset).await,
-        "llama-hf" => run_with_llama_hf(model, &quant_str, preset).await,
-        "ollama" => run_with_ollama(model, &quant_str).await,
-        _ => anyhow::bail!("Unknown runtime: {}. Use 'llama-cpp', 'llama-hf', or 'ollama'", runtime_name),
+fn print_source(source: &ModelSource) {
+    match source {
+        ModelSource::File(path) => {
+            let filename = path.file_name().unwrap_or_default().to_string_lossy();
+            println!("{} File: {}", "→".bright_blue(), filename.bright_yellow());
+        }
+        ModelSource::HuggingFace(hf_model) => {
+            println!(
+                "{} Running directly from HuggingFace: {}",
+                "▶".bright_green(),
+                hf_model.bright_yellow()
+            );
+            println!("{} No download needed - streaming from HF", "ℹ".bright_blue());
+        }
     }
 }
 
-async fn run_with_llama_cpp(model_path: &std::path::Path, preset: Option<String>) -> Result<()> {
-    // Check if llama-cli or llama.cpp exists
-    let llama_cmd = if command_exists("llama-cli") {
-        "llama-cli"
-    } else if command_exists("llama") {
-        "llama"
-    } else if command_exists("main") {
-        "main"
-    } else {
-        println!("{} llama.cpp not found!", "✗".bright_red());
-        println!("\n{} Install it first:", "→".bright_blue());
-        println!("  Termux: {}", "pkg install llama-cpp".bright_green());
-        println!("  Other: {}", "yuy runtime install llama-cpp".bright_green());
-        return Ok(());
+/// The one-shot prompt: `-p` text, else whatever is piped on `stdin`.
+/// `None` means an interactive chat. Stdin isn't touched when `-p` is
+/// given, so an open but idle stdin (e.g. in CI) can't hang the run.
+fn read_prompt(flag: Option<String>, mut stdin: impl Read, is_terminal: bool) -> Result<Option<String>> {
+    if flag.is_some() || is_terminal {
+        return Ok(flag);
+    }
+    let mut input = String::new();
+    stdin.read_to_string(&mut input)?;
+    if input.trim().is_empty() {
+        anyhow::bail!("No prompt given: stdin is empty. Pass one with -p \"...\"");
+    }
+    Ok(Some(input.trim_end().to_string()))
+}
+
+/// Streams a single reply to `out`. The exchange is saved only when a
+/// session was asked for with `--session` or `--resume`.
+async fn one_shot(
+    store: &SessionStore,
+    session: &mut Session,
+    backend: &Backend,
+    sampling: &Preset,
+    prompt: String,
+    persist: bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    session.messages.push(Message::new(Role::User, prompt));
+
+    let mut out = LastByte { inner: out, last: None };
+    let reply = backend.complete(&session.conversation(), sampling, &mut out).await?.text;
+    if out.last != Some(b'\n') {
+        writeln!(out)?;
+    }
+
+    if persist {
+        session.messages.push(Message::new(Role::Assistant, reply));
+        store.save(session)?;
+    }
+    Ok(())
+}
+
+/// Remembers the last byte written, to end output with exactly one newline.
+struct LastByte<W> {
+    inner: W,
+    last: Option<u8>,
+}
+
+impl<W: Write> Write for LastByte<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        if n > 0 {
+            self.last = Some(buf[n - 1]);
+        }
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Picks the session to continue, or starts a new one.
+///
+/// `--resume` reloads the model's most recent session (or `--session <name>`);
+/// `--session <name>` on its own continues that session or creates it.
+fn open_session(store: &SessionStore, model: &str, resume: bool, name: Option<String>, quiet: bool) -> Result<Session> {
+    let session = match (resume, name) {
+        (true, Some(name)) => Some(store.load(&name)?),
+        (true, None) => Some(store.latest_for(model)?.ok_or_else(|| {
+            anyhow::anyhow!("No saved sessions for {} to resume. Start one with: yuy run {}", model, model)
+        })?),
+        (false, Some(name)) if store.exists(&name)? => Some(store.load(&name)?),
+        (false, Some(name)) => return Ok(Session::new(&name, model)),
+        (false, None) => None,
+    };
+
+    let Some(session) = session else {
+        return Ok(Session::new(&generate_name(), model));
     };
+    if !session.model.eq_ignore_ascii_case(model) {
+        anyhow::bail!(
+            "Session '{}' was started with {}. Resume it with: yuy run {} --session {}",
+            session.name,
+            session.model,
+            session.model,
+            session.name
+        );
+    }
 
+    if quiet {
+        return Ok(session);
+    }
     println!(
-        "{} Starting llama.cpp interactive mode...",
-        "▶".bright_green()
+        "{} Resuming session {} ({} messages)",
+        "↺".bright_blue(),
+        session.name.bright_yellow(),
+        session.messages.len()
     );
+    // Replay the last exchange for context
+    let start = session.messages.len().saturating_sub(2);
+    for message in &session.messages[start..] {
+        print_message(message);
+    }
     println!();
+    Ok(session)
+}
+
+/// Applies `--template` to the session's system prompt and returns the chat
+/// format to use. Without the flag, a resumed session keeps its template.
+fn apply_template(session: &mut Session, name: Option<String>, vars: &[String]) -> Result<ChatFormat> {
+    let Some(name) = name else {
+        if !vars.is_empty() {
+            anyhow::bail!("--var needs a template. Pick one with --template (see: yuy template list)");
+        }
+        return match &session.template {
+            Some(name) => match TemplateStore::open()?.find(name) {
+                Ok(template) => Ok(template.format),
+                Err(e) => {
+                    eprintln!("{} {}; using the default chat format", "⚠".bright_yellow(), e);
+                    Ok(ChatFormat::default())
+                }
+            },
+            None => Ok(ChatFormat::default()),
+        };
+    };
+
+    let (template, system) = render_template(&name, vars)?;
+    session.system_prompt = Some(system);
+    session.template = Some(template.name);
+    Ok(template.format)
+}
+
+/// Looks up `--template` and renders its system prompt with `--var` values.
+pub fn render_template(name: &str, vars: &[String]) -> Result<(Template, String)> {
+    let template = TemplateStore::open()?.find(name)?;
+    let values = vars
+        .iter()
+        .map(|v| parse_var(v))
+        .collect::<Result<BTreeMap<_, _>>>()?;
+    let system = template.render(&values)?;
+    Ok((template, system))
+}
+
+/// The flags over the `[models.<name>]` defaults for `model`. The context
+/// size is settled into `sampling`, which every backend reads it from:
+/// `--ctx`, then the preset's `ctx_size`, then the model default.
+pub fn runtime_options(model: &str, flags: &RuntimeOptions, sampling: &mut Preset) -> Result<RuntimeOptions> {
+    let defaults = load_config()?.model_options(model);
+    flags.validate()?;
+    defaults
+        .validate()
+        .map_err(|e| anyhow::anyhow!("{} ([models.{}] in the config)", e, model))?;
+    let mut options = defaults.with_overrides(flags);
+    sampling.ctx_size = flags.ctx_size.or(sampling.ctx_size).or(defaults.ctx_size);
+    options.ctx_size = sampling.ctx_size;
+    Ok(options)
+}
 
-    // Configure parameters based on preset
-    let (temp, top_p) = match preset.as_deref() {
-        Some("creative") => (0.8, 0.9),
-        Some("precise") => (0.3, 0.5),
-        Some("balanced") | None => (0.6, 0.7),
-        _ => (0.6, 0.7),
+/// The first runtime in `chain` that can serve the model, with its
+/// backend. Runtimes that are missing or don't have the model are skipped
+/// with a note; when none is left the error is `NoRuntime`.
+pub async fn pick_backend(
+    entry: &ModelEntry,
+    chain: &[String],
+    quant: &str,
+    format: ChatFormat,
+    sampling: &Preset,
+    options: &RuntimeOptions,
+    slots: usize,
+) -> Result<(String, Backend)> {
+    first_available(&entry.name, chain, |runtime| async move {
+        select_backend(entry, &runtime, quant, format, sampling, options, slots).await
+    })
+    .await
+}
+
+/// `select` for each runtime of `chain` in turn, until one isn't a
+/// `RuntimeUnavailable`.
+async fn first_available<T, F: Future<Output = Result<T>>>(
+    model: &str,
+    chain: &[String],
+    mut select: impl FnMut(String) -> F,
+) -> Result<(String, T)> {
+    let mut tried = Vec::new();
+    for runtime in chain {
+        match select(runtime.clone()).await {
+            Ok(selected) => {
+                for skipped in &tried {
+                    let RuntimeUnavailable { runtime: name, reason } = skipped;
+                    eprintln!("{} {}: {}", "⚠".bright_yellow(), name, reason);
+                }
+                if !tried.is_empty() {
+                    eprintln!("{} Falling back to {}", "→".bright_blue(), runtime.bright_green());
+                }
+                return Ok((runtime.clone(), selected));
+            }
+            Err(e) => tried.push(e.downcast::<RuntimeUnavailable>()?),
+        }
+    }
+    Err(NoRuntime { model: model.to_string(), tried }.into())
+}
+
+/// Builds the backend for `runtime`. A missing runtime, server or model
+/// file is a `RuntimeUnavailable` with an install or download hint.
+/// llama.cpp models are served by `llama-server` with room for `slots`
+/// requests at once when it is installed.
+async fn select_backend(
+    entry: &ModelEntry,
+    runtime: &str,
+    quant: &str,
+    format: ChatFormat,
+    sampling: &Preset,
+    options: &RuntimeOptions,
+    slots: usize,
+) -> Result<Backend> {
+    let unavailable = |reason: String| -> anyhow::Error {
+        RuntimeUnavailable { runtime: runtime.to_string(), reason }.into()
     };
+    match runtime {
+        "llama-cpp" => {
+            let model = entry.name.as_str();
+            let model_dir = model_dir(model)?;
+            if !model_dir.exists() {
+                return Err(unavailable(format!(
+                    "Model '{}' is not downloaded yet. Download it first: yuy download {}",
+                    model, model
+                )));
+            }
 
-    // Run llama.cpp
-    let status = Command::new(llama_cmd)
-        .arg("-m")
-        .arg(model_path)
-        .arg("--interactive")
-        .arg("--temp")
-        .arg(temp.to_string())
-        .arg("--top-p")
-        .arg(top_p.to_string())
-        .arg("-c")
-        .arg("4096")
-        .status()
-        .context("Failed to execute llama.cpp")?;
+            // Find GGUF file
+            let local = local_variants(&model_dir);
+            let Some((filename, _)) = local.iter().find(|(_, q)| q.eq_ignore_ascii_case(quant)) else {
+                let available: Vec<_> = local.iter().map(|(_, q)| q.as_str()).collect();
+                return Err(unavailable(format!(
+                    "No '{}' file downloaded for {} (local: {}). Try: yuy download {} --quant {}",
+                    quant,
+                    model,
+                    if available.is_empty() { "none".to_string() } else { available.join(", ") },
+                    model,
+                    quant
+                )));
+            };
 
-    if !status.success() {
-        anyhow::bail!("llama.cpp exited with error");
+            let source = ModelSource::File(model_dir.join(filename));
+            llama_backend(runtime, Backend::find_llama_cli(), source, format, sampling, options, slots).await
+        }
+        "llama-hf" => {
+            // HuggingFace format: OpceanAI/Yuuki-best:F32
+            let hf_model = format!("{}:{}", entry.download_repo(), quant.to_uppercase());
+            let cli = command_exists("llama-cli").then_some("llama-cli");
+            llama_backend(runtime, cli, ModelSource::HuggingFace(hf_model), format, sampling, options, slots).await
+        }
+        "ollama" => {
+            // Options Ollama can't take rule it out before contacting it
+            options.ollama_options().map_err(|e| unavailable(e.to_string()))?;
+            let client = OllamaClient::new();
+            // Construct ollama model name: aguitachan3/yuuki-best:f32
+            let model = entry.ollama_model(quant);
+            ensure_ollama_model(&client, &model).await?;
+            Ok(Backend::Ollama { client, model, options: options.clone() })
+        }
+        _ => anyhow::bail!("Unknown runtime: {}. Use 'llama-cpp', 'llama-hf', or 'ollama'", runtime),
     }
+}
 
-    Ok(())
+/// Starts llama-server when it is installed, since it keeps the model
+/// loaded between turns; otherwise runs `cli` once per turn.
+async fn llama_backend(
+    runtime: &str,
+    cli: Option<&str>,
+    source: ModelSource,
+    format: ChatFormat,
+    sampling: &Preset,
+    options: &RuntimeOptions,
+    slots: usize,
+) -> Result<Backend> {
+    if let Some(binary) = LlamaServer::find() {
+        let spinner = ProgressBar::new_spinner();
+        spinner.set_message(format!("Loading the model in {}...", binary));
+        spinner.enable_steady_tick(Duration::from_millis(120));
+        let ctx_size = sampling.ctx_size.unwrap_or(DEFAULT_CONTEXT);
+        let timeout = load_config()?.server_timeout.map_or(DEFAULT_READY_TIMEOUT, Duration::from_secs);
+        let server = LlamaServer::start(binary, &source, format, ctx_size, slots, options, timeout).await;
+        spinner.finish_and_clear();
+        return Ok(Backend::LlamaServer(Arc::new(server?)));
+    }
+
+    let Some(binary) = cli else {
+        return Err(RuntimeUnavailable {
+            runtime: runtime.to_string(),
+            reason: "llama.cpp not found. Install it with: yuy runtime install llama-cpp (Termux: pkg install llama-cpp)"
+                .to_string(),
+        }
+        .into());
+    };
+    Ok(Backend::LlamaCpp { binary: binary.to_string(), model: source, format, options: options.clone() })
 }
 
-async fn run_with_ollama(model: &str, quant: &str) -> Result<()> {
-    if !command_exists("ollama") {
-        println!("{} ollama not found!", "✗".bright_red());
-        println!("\n{} Install it first:", "→".bright_blue());
-        println!("  Termux: {}", "pkg install ollama".bright_green());
-        println!("  Other: {}", "yuy runtime install ollama".bright_green());
+/// Checks that the Ollama server is up and pulls `model` if it doesn't
+/// have it yet.
+async fn ensure_ollama_model(client: &OllamaClient, model: &str) -> Result<()> {
+    if let Err(e) = client.version().await {
+        let hint = if command_exists("ollama") {
+            "Start it with: ollama serve".to_string()
+        } else {
+            "Install it with: yuy runtime install ollama (Termux: pkg install ollama)".to_string()
+        };
+        return Err(RuntimeUnavailable {
+            runtime: "ollama".to_string(),
+            reason: format!("{}. {} (or point OLLAMA_HOST at a running server)", e, hint),
+        }
+        .into());
+    }
+    if client.has_model(model).await? {
         return Ok(());
     }
 
-    // Construct ollama model name: aguitachan3/yuuki-best:f32
-    let ollama_model = format!("{}/{}:{}", OLLAMA_ORG, model.to_lowercase(), quant);
+    eprintln!("{} Pulling {} into Ollama...", "⬇".bright_cyan(), model.bright_yellow());
+    let pb = ProgressBar::new(0);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg} [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")?
+            .progress_chars("#>-"),
+    );
+    let mut layer = None;
+    client
+        .pull(model, |update| {
+            // Each layer is its own download
+            if update.digest.is_some() && update.digest != layer {
+                layer = update.digest.clone();
+                pb.set_length(update.total.unwrap_or(0));
+                pb.set_position(0);
+            }
+            if let Some(completed) = update.completed {
+                pb.set_position(completed);
+            }
+            pb.set_message(update.status.clone());
+        })
+        .await?;
+    pb.finish_and_clear();
+    eprintln!("{} Pulled {}", "✓".bright_green(), model.bright_yellow());
+    Ok(())
+}
 
+/// Reads prompts from stdin until `/exit` or EOF, saving the session after
+/// every reply.
+async fn chat_loop(store: &SessionStore, session: &mut Session, backend: &Backend, sampling: &Preset) -> Result<()> {
     println!(
-        "{} Starting Ollama with {}...",
+        "{} Chat started. Type {} for commands, {} to leave.",
         "▶".bright_green(),
-        ollama_model.bright_yellow()
+        "/help".bright_green(),
+        "/exit".bright_green()
     );
     println!();
-    
-    let status = Command::new("ollama")
-        .arg("run")
-        .arg(&ollama_model)
-        .status()
-        .context("Failed to execute ollama")?;
 
-    if !status.success() {
-        println!("\n{} Model not found in Ollama.", "ℹ".bright_blue());
-        println!("{} Pull it first: {}", "→".bright_blue(), format!("ollama pull {}", ollama_model).bright_green());
+    let mut lines = stdin_lines();
+    let mut stdout = std::io::stdout();
+    let mut interrupted = false;
+    loop {
+        print!("{} ", "you ›".bright_cyan().bold());
+        stdout.flush()?;
+
+        let line = tokio::select! {
+            line = lines.recv() => line,
+            _ = backend.interrupted() => {
+                interrupted = true;
+                println!();
+                break;
+            }
+        };
+        let Some(line) = line.transpose()? else {
+            println!();
+            break;
+        };
+        let input = line.trim();
+        if input.is_empty() {
+            continue;
+        }
+
+        if let Some(command) = input.strip_prefix('/') {
+            let (name, arg) = command.split_once(' ').unwrap_or((command, ""));
+            match name {
+                "exit" | "quit" => break,
+                "help" => {
+                    println!("  {}  set the system prompt", "/system <text>".bright_green());
+                    println!("  {}          forget the conversation so far", "/clear".bright_green());
+                    println!("  {}   leave (the session is saved)", "/exit, /quit".bright_green());
+                }
+                "system" => {
+                    let prompt = arg.trim();
+                    session.system_prompt = (!prompt.is_empty()).then(|| prompt.to_string());
+                    store.save(session)?;
+                    println!("{} System prompt updated", "✓".bright_green());
+                }
+                "clear" => {
+                    session.messages.clear();
+                    store.save(session)?;
+                    println!("{} Conversation cleared", "✓".bright_green());
+                }
+                _ => println!("{} Unknown command /{}. Try /help", "⚠".bright_yellow(), name),
+            }
+            continue;
+        }
+
+        session.messages.push(Message::new(Role::User, input));
+        print!("{} ", "yuuki ›".bright_magenta().bold());
+        stdout.flush()?;
+
+        match backend.complete(&session.conversation(), sampling, &mut stdout).await {
+            Ok(reply) => {
+                println!();
+                println!();
+                session.messages.push(Message::new(Role::Assistant, reply.text));
+                store.save(session)?;
+            }
+            Err(e) => {
+                // Keep the history consistent so the prompt can be retried
+                session.messages.pop();
+                println!();
+                if e.downcast_ref::<Interrupted>().is_some() {
+                    interrupted = true;
+                    break;
+                }
+                println!("{} {}", "✗".bright_red(), e);
+            }
+        }
     }
 
+    if !session.messages.is_empty() {
+        println!(
+            "{} Session saved. Continue with: {}",
+            "✓".bright_green(),
+            format!("yuy run {} --resume --session {}", session.model, session.name).bright_green()
+        );
+    }
+    if interrupted {
+        return Err(Interrupted.into());
+    }
     Ok(())
 }
 
-async fn run_with_llama_hf(model: &str, quant: &str, preset: Option<String>) -> Result<()> {
-    if !command_exists("llama-cli") {
-        println!("{} llama-cli not found!", "✗".bright_red());
-        println!("\n{} Install llama.cpp first:", "→".bright_blue());
-        println!("  Termux: {}", "pkg install llama-cpp".bright_green());
-        return Ok(());
-    }
+/// Lines typed on stdin, read on their own thread so waiting for one can
+/// be interrupted. The channel closes at EOF.
+fn stdin_lines() -> tokio::sync::mpsc::Receiver<std::io::Result<String>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1);
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lines() {
+            if tx.blocking_send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
 
-    // HuggingFace format: OpceanAI/Yuuki-best:F32
-    let hf_model = format!("{}/{}:{}", HF_ORG, model, quant.to_uppercase());
+pub fn print_message(message: &Message) {
+    let label = match message.role {
+        Role::System => "system ›".bright_black().bold(),
+        Role::User => "you ›".bright_cyan().bold(),
+        Role::Assistant => "yuuki ›".bright_magenta().bold(),
+    };
+    println!("{} {}", label, message.content);
+}
 
-    println!(
-        "{} Running directly from HuggingFace: {}",
-        "▶".bright_green(),
-        hf_model.bright_yellow()
+/// Quantization when nothing names one: the only downloaded file, or the
+/// largest downloaded one that fits in memory. `DEFAULT_QUANT` when none
+/// is downloaded.
+pub fn auto_quant(entry: &ModelEntry) -> Result<Setting<String>> {
+    let model_dir = model_dir(&entry.name)?;
+    let local = local_variants(&model_dir);
+    if let [(_, quant)] = local.as_slice() {
+        return Ok(Setting::new(quant.clone(), Source::Auto("the only downloaded file".to_string())));
+    }
+
+    let variants: Vec<Variant> = local
+        .iter()
+        .filter_map(|(filename, quant)| {
+            let size = std::fs::metadata(model_dir.join(filename)).ok()?.len();
+            Some(Variant { quant: quant.clone(), size })
+        })
+        .collect();
+    let recommendation = recommend_quant(
+        &variants,
+        entry.parameters.as_deref(),
+        DEFAULT_CONTEXT,
+        &SystemInfo::detect(),
+        &detect_platform(),
+        None,
     );
-    println!("{} No download needed - streaming from HF", "ℹ".bright_blue());
-    println!();
+    Ok(match recommendation {
+        Some(rec) if rec.fits => Setting::new(rec.quant, Source::Auto("best downloaded fit".to_string())),
+        Some(rec) => Setting::new(rec.quant, Source::Auto("smallest download, may not fit".to_string())),
+        None => Setting::new(DEFAULT_QUANT.to_string(), Source::Default),
+    })
+}
 
-    // Configure parameters based on preset
-    let (temp, top_p) = match preset.as_deref() {
-        Some("creative") => (0.8, 0.9),
-        Some("precise") => (0.3, 0.5),
-        Some("balanced") | None => (0.6, 0.7),
-        _ => (0.6, 0.7),
+/// GGUF files in `model_dir` with the quant parsed from each name.
+pub fn local_variants(model_dir: &std::path::Path) -> Vec<(String, String)> {
+    let Ok(entries) = std::fs::read_dir(model_dir) else {
+        return Vec::new();
     };
+    let mut variants: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let quant = parse_quant(&filename)?;
+            Some((filename, quant))
+        })
+        .collect();
+    variants.sort();
+    variants
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
 
-    // Run llama-cli with -hf flag
-    let status = Command::new("llama-cli")
-        .arg("-hf")
-        .arg(&hf_model)
-        .arg("--temp")
-        .arg(temp.to_string())
-        .arg("--top-p")
-        .arg(top_p.to_string())
-        .arg("-c")
-        .arg("4096")
-        .status()
-        .context("Failed to execute llama-cli with HuggingFace")?;
+    #[test]
+    fn resumes_the_latest_session_for_the_model() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::at(dir.path());
+        let mut older = Session::new("older", "Yuuki-NxG-3B");
+        older.messages.push(Message::new(Role::User, "hi"));
+        store.save(&mut older).unwrap();
+        let mut newer = Session::new("newer", "Yuuki-NxG-vl");
+        newer.updated_at = older.updated_at + 10;
+        store.save(&mut newer).unwrap();
 
-    if !status.success() {
-        anyhow::bail!("llama-cli exited with error");
+        assert_eq!(open_session(&store, "Yuuki-NxG-3B", true, None, true).unwrap().name, "older");
+        assert_eq!(open_session(&store, "Yuuki-NxG-vl", true, None, true).unwrap().name, "newer");
+        assert!(open_session(&store, "Yuuki-best", true, None, true).is_err());
+        // Naming another model's session is still refused
+        assert!(open_session(&store, "Yuuki-NxG-3B", true, Some("newer".into()), true).is_err());
     }
 
-    Ok(())
+    #[tokio::test]
+    async fn falls_through_unavailable_runtimes() {
+        let chain: Vec<String> = ["llama-cpp", "llama-hf", "ollama"].map(String::from).to_vec();
+        let unavailable = |runtime: &str| -> anyhow::Error {
+            RuntimeUnavailable { runtime: runtime.to_string(), reason: format!("no {}", runtime) }.into()
+        };
+
+        let mut asked = Vec::new();
+        let picked = first_available("Yuuki-best", &chain, |runtime| {
+            asked.push(runtime.clone());
+            std::future::ready(match runtime.as_str() {
+                "llama-hf" => Ok(runtime.len()),
+                _ => Err(unavailable(&runtime)),
+            })
+        })
+        .await
+        .unwrap();
+        assert_eq!(picked, ("llama-hf".to_string(), 8));
+        assert_eq!(asked, ["llama-cpp", "llama-hf"]);
+
+        let err = first_available::<(), _>("Yuuki-best", &chain, |runtime| std::future::ready(Err(unavailable(&runtime))))
+            .await
+            .unwrap_err();
+        let none = err.downcast_ref::<NoRuntime>().unwrap();
+        let tried: Vec<_> = none.tried.iter().map(|t| t.runtime.as_str()).collect();
+        assert_eq!(tried, ["llama-cpp", "llama-hf", "ollama"]);
+
+        // Anything else stops the search
+        let err = first_available::<(), _>("Yuuki-best", &chain, |_| async { anyhow::bail!("corrupt model") })
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "corrupt model");
+    }
+
+    /// A reader that fails the test if anything reads from it.
+    struct Untouchable;
+
+    impl Read for Untouchable {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            panic!("stdin was read");
+        }
+    }
+
+    #[test]
+    fn reads_the_prompt_from_the_flag_or_stdin() {
+        let flag = Some("Write a haiku".to_string());
+        assert_eq!(read_prompt(flag.clone(), Untouchable, false).unwrap(), flag);
+        assert_eq!(read_prompt(flag.clone(), Untouchable, true).unwrap(), flag);
+        assert_eq!(read_prompt(None, Untouchable, true).unwrap(), None);
+        assert_eq!(read_prompt(None, &b"notes\n\n"[..], false).unwrap().as_deref(), Some("notes"));
+        assert!(read_prompt(None, &b" \n"[..], false).unwrap_err().to_string().contains("stdin is empty"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn one_shot_prints_the_reply_and_saves_only_when_asked() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = fake_binary(dir.path(), "llama-cli", "printf 'Hello there!'\n");
+        let backend = Backend::LlamaCpp {
+            binary: script.to_string_lossy().to_string(),
+            model: ModelSource::File(dir.path().join("model.gguf")),
+            format: ChatFormat::Chatml,
+            options: RuntimeOptions::default(),
+        };
+        let store = SessionStore::at(dir.path().join("sessions"));
+
+        let mut out = Vec::new();
+        let mut session = Session::new("scratch", "Yuuki-best");
+        one_shot(&store, &mut session, &backend, &Preset::default(), "hi".into(), false, &mut out).await.unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "Hello there!\n");
+        assert!(!store.exists("scratch").unwrap());
+
+        let mut session = Session::new("notes", "Yuuki-best");
+        one_shot(&store, &mut session, &backend, &Preset::default(), "hi".into(), true, &mut Vec::new()).await.unwrap();
+        let saved = store.load("notes").unwrap();
+        assert_eq!(saved.messages.len(), 2);
+        assert_eq!(saved.messages[1].content, "Hello there!");
+    }
 }
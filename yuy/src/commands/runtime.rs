@@ -2,6 +2,7 @@ use anyhow::Result;
 use colored::Colorize;
 use std::process::Command;
 use crate::cli::RuntimeAction;
+use crate::ollama::OllamaClient;
 use crate::utils::{command_exists, detect_platform, Platform};
 
 pub async fn execute(action: RuntimeAction) -> Result<()> {
@@ -19,7 +20,8 @@ async fn check_runtimes() -> Result<()> {
     // Check llama.cpp
     let llama_installed = command_exists("llama-cli")
         || command_exists("llama")
-        || command_exists("main");
+        || command_exists("main")
+        || command_exists("llama-server");
 
     if llama_installed {
         println!("{} {}", "✓".bright_green(), "llama.cpp".bright_white().bold());
@@ -32,6 +34,11 @@ async fn check_runtimes() -> Result<()> {
                 }
             }
         }
+        if command_exists("llama-server") {
+            println!("  {} llama-server found; models stay loaded between turns", "→".bright_blue());
+        } else {
+            println!("  {} llama-server not found; each turn starts llama-cli", "→".bright_black());
+        }
     } else {
         println!("{} {}", "✗".bright_red(), "llama.cpp".bright_white().bold());
         println!("  {} Not installed", "→".bright_black());
@@ -50,6 +57,21 @@ async fn check_runtimes() -> Result<()> {
                 println!("  {} {}", "→".bright_blue(), version.trim().bright_black());
             }
         }
+        let client = OllamaClient::new();
+        match client.version().await {
+            Ok(version) => println!(
+                "  {} Server running at {} (v{})",
+                "→".bright_blue(),
+                client.host(),
+                version
+            ),
+            Err(_) => println!(
+                "  {} Server not running at {}. Start it with: {}",
+                "→".bright_yellow(),
+                client.host(),
+                "ollama serve".bright_green()
+            ),
+        }
     } else {
         println!("{} {}", "✗".bright_red(), "ollama".bright_white().bold());
         println!("  {} Not installed", "→".bright_black());
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::PathBuf;
+use crate::cli::{ExportFormat, SessionsAction};
+use crate::commands::run::print_message;
+use crate::session::SessionStore;
+use crate::utils::format_timestamp;
+
+pub async fn execute(action: SessionsAction) -> Result<()> {
+    let store = SessionStore::open()?;
+    match action {
+        SessionsAction::List => list(&store),
+        SessionsAction::Show { name } => show(&store, &name),
+        SessionsAction::Delete { name } => {
+            store.delete(&name)?;
+            println!("{} Deleted session {}", "✓".bright_green(), name.bright_yellow());
+            Ok(())
+        }
+        SessionsAction::Export { name, format, output } => export(&store, &name, format, output),
+    }
+}
+
+fn list(store: &SessionStore) -> Result<()> {
+    println!("{}", "💬 Saved Sessions".bright_cyan().bold());
+    println!();
+
+    let sessions = store.list()?;
+    if sessions.is_empty() {
+        println!("{} No sessions yet.", "ℹ".bright_blue());
+        println!();
+        println!("{} Start one:", "→".bright_blue());
+        println!("  {}", "yuy run Yuuki-NxG-3B".bright_green());
+        return Ok(());
+    }
+
+    for session in &sessions {
+        println!(
+            "{} {}  {}  {}",
+            "•".bright_green(),
+            session.name.bright_yellow().bold(),
+            session.model.bright_white(),
+            format!(
+                "{} messages, updated {}",
+                session.messages.len(),
+                format_timestamp(session.updated_at)
+            )
+            .bright_black()
+        );
+        if let Some(first) = session.messages.first() {
+            println!("  {}", preview(&first.content).bright_black());
+        }
+    }
+
+    println!();
+    println!(
+        "{} Location: {}",
+        "📁".bright_blue(),
+        store.dir().display().to_string().bright_black()
+    );
+    Ok(())
+}
+
+fn show(store: &SessionStore, name: &str) -> Result<()> {
+    let session = store.load(name)?;
+
+    println!("{} {}", "Session:".bright_cyan().bold(), session.name.bright_yellow().bold());
+    println!("{} {}", "Model:".bright_cyan(), session.model);
+    if let Some(quant) = &session.quant {
+        println!("{} {}", "Quantization:".bright_cyan(), quant);
+    }
+    if let Some(runtime) = &session.runtime {
+        println!("{} {}", "Runtime:".bright_cyan(), runtime);
+    }
+    if let Some(preset) = &session.preset {
+        println!("{} {}", "Preset:".bright_cyan(), preset);
+    }
+    if let Some(template) = &session.template {
+        println!("{} {}", "Template:".bright_cyan(), template);
+    }
+    println!("{} {}", "Created:".bright_cyan(), format_timestamp(session.created_at));
+    println!("{} {}", "Updated:".bright_cyan(), format_timestamp(session.updated_at));
+    println!();
+
+    for message in session.conversation() {
+        print_message(&message);
+        println!();
+    }
+    Ok(())
+}
+
+fn export(store: &SessionStore, name: &str, format: ExportFormat, output: Option<PathBuf>) -> Result<()> {
+    let session = store.load(name)?;
+    let content = match format {
+        ExportFormat::Markdown => session.to_markdown(),
+        ExportFormat::Json => serde_json::to_string_pretty(&session)? + "\n",
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, content)?;
+            eprintln!(
+                "{} Exported {} to {}",
+                "✓".bright_green(),
+                name.bright_yellow(),
+                path.display().to_string().bright_white()
+            );
+        }
+        None => print!("{}", content),
+    }
+    Ok(())
+}
+
+/// First line of a message, shortened for listings.
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or("");
+    if line.chars().count() > 60 {
+        format!("{}…", line.chars().take(60).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::Result;
 use colored::Colorize;
-use crate::config::{get_yuuki_dir, get_models_dir};
+use crate::config::{get_config_dir, get_config_path, get_models_dir, save_config, Config};
 use crate::utils::{command_exists, detect_platform};
 
 pub async fn execute() -> Result<()> {
@@ -12,19 +12,24 @@ pub async fn execute() -> Result<()> {
 
     // Step 1: Create directories
     println!("{} Creating directories...", "1.".bright_white().bold());
-    let yuuki_dir = get_yuuki_dir()?;
-    let models_dir = get_models_dir()?;
-
-    println!(
-        "  {} {}",
-        "✓".bright_green(),
-        yuuki_dir.display().to_string().bright_yellow()
-    );
-    println!(
-        "  {} {}",
-        "✓".bright_green(),
-        models_dir.display().to_string().bright_yellow()
-    );
+    for dir in [get_config_dir()?, get_models_dir()?] {
+        std::fs::create_dir_all(&dir)?;
+        println!(
+            "  {} {}",
+            "✓".bright_green(),
+            dir.display().to_string().bright_yellow()
+        );
+    }
+    // The only place a config file is written without a setting changing
+    let config_path = get_config_path()?;
+    if !config_path.exists() {
+        save_config(&Config::default())?;
+        println!(
+            "  {} {}",
+            "✓".bright_green(),
+            config_path.display().to_string().bright_yellow()
+        );
+    }
     println!();
 
     // Step 2: Check platform
@@ -0,0 +1,126 @@
+use anyhow::Result;
+use colored::Colorize;
+use crate::cli::TemplateAction;
+use crate::template::{load_file, Template, TemplateStore};
+use crate::utils::open_in_editor;
+
+pub async fn execute(action: TemplateAction) -> Result<()> {
+    let store = TemplateStore::open()?;
+    match action {
+        TemplateAction::List => list(&store),
+        TemplateAction::Show { name } => show(&store, &name),
+        TemplateAction::New { name, from } => new(&store, &name, from),
+        TemplateAction::Edit { name } => edit(&store, &name),
+    }
+}
+
+fn list(store: &TemplateStore) -> Result<()> {
+    println!("{}", "📝 Prompt Templates".bright_cyan().bold());
+    println!();
+
+    for (template, user) in store.list()? {
+        let source = if user { "user" } else { "built-in" };
+        println!(
+            "{} {}  {}",
+            "•".bright_green(),
+            template.name.bright_yellow().bold(),
+            format!("({})", source).bright_black()
+        );
+        if !template.description.is_empty() {
+            println!("  {}", template.description.bright_white());
+        }
+    }
+
+    println!();
+    println!(
+        "{} Use one: {}",
+        "→".bright_blue(),
+        "yuy run <model> --template translation --var language=Spanish".bright_green()
+    );
+    Ok(())
+}
+
+fn show(store: &TemplateStore, name: &str) -> Result<()> {
+    let template = store.find(name)?;
+
+    println!("{} {}", "Template:".bright_cyan().bold(), template.name.bright_yellow().bold());
+    if !template.description.is_empty() {
+        println!("{} {}", "About:".bright_cyan(), template.description);
+    }
+    println!("{} {}", "Format:".bright_cyan(), format!("{:?}", template.format).to_lowercase());
+    let path = store.path(&template.name)?;
+    if path.exists() {
+        println!("{} {}", "File:".bright_cyan(), path.display().to_string().bright_black());
+    }
+
+    let placeholders = template.placeholders();
+    if !placeholders.is_empty() {
+        println!("{}", "Variables:".bright_cyan());
+        for name in placeholders {
+            let default = match template.variables.get(&name) {
+                Some(value) if !value.is_empty() => format!("default: {}", value),
+                _ => "required".to_string(),
+            };
+            println!("  {} {} {}", "•".bright_green(), name.bright_white(), format!("({})", default).bright_black());
+        }
+    }
+
+    println!();
+    println!("{}", "System prompt:".bright_cyan());
+    println!("{}", template.system);
+    Ok(())
+}
+
+fn new(store: &TemplateStore, name: &str, from: Option<String>) -> Result<()> {
+    let path = store.path(name)?;
+    if path.exists() {
+        anyhow::bail!("Template '{}' already exists. Edit it with: yuy template edit {}", name, name);
+    }
+
+    let mut template = match from {
+        Some(base) => store.find(&base)?,
+        None => Template::skeleton(name),
+    };
+    template.name = name.to_string();
+    let path = store.save(&template)?;
+
+    println!(
+        "{} Created template {} at {}",
+        "✓".bright_green(),
+        name.bright_yellow(),
+        path.display().to_string().bright_white()
+    );
+    println!(
+        "{} Edit it with: {}",
+        "→".bright_blue(),
+        format!("yuy template edit {}", name).bright_green()
+    );
+    Ok(())
+}
+
+fn edit(store: &TemplateStore, name: &str) -> Result<()> {
+    let mut path = store.path(name)?;
+    if !path.exists() {
+        // Editing a built-in creates a user copy that replaces it
+        let template = store.find(name)?;
+        path = store.save(&template)?;
+        println!(
+            "{} Copied built-in template {} to {}",
+            "ℹ".bright_blue(),
+            template.name.bright_yellow(),
+            path.display().to_string().bright_white()
+        );
+    }
+
+    open_in_editor(&path)?;
+
+    // Catch mistakes now rather than at the next `yuy run`
+    let template = load_file(&path)?;
+    println!(
+        "{} Saved template {} ({} variables)",
+        "✓".bright_green(),
+        template.name.bright_yellow(),
+        template.placeholders().len()
+    );
+    Ok(())
+}
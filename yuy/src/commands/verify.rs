@@ -0,0 +1,107 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::Path;
+use crate::commands::download::sha256_file;
+use crate::config::model_dir;
+use crate::hf::HfClient;
+use crate::registry::Registry;
+
+pub async fn execute(model: &str) -> Result<()> {
+    println!("{}", "🔒 Verify Model".bright_cyan().bold());
+    println!();
+
+    let entry = Registry::load()?.resolve(model)?.entry;
+    let model_dir = model_dir(&entry.name)?;
+
+    if !model_dir.exists() {
+        anyhow::bail!("Model '{}' is not downloaded.", model);
+    }
+
+    let mut files: Vec<_> = std::fs::read_dir(&model_dir)?
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".gguf"))
+        .collect();
+    files.sort();
+
+    if files.is_empty() {
+        println!("{} No GGUF files found for '{}'.", "ℹ".bright_blue(), model.bright_yellow());
+        return Ok(());
+    }
+
+    verify_files(&HfClient::new(), entry.download_repo(), &model_dir, &files).await
+}
+
+/// Checks `files` in `model_dir` against the checksums published in `repo`.
+async fn verify_files(hf: &HfClient, repo: &str, model_dir: &Path, files: &[String]) -> Result<()> {
+    println!("{} Fetching checksums from {}...", "→".bright_blue(), repo.bright_yellow());
+    let remote = hf.paths_info(repo, files).await?;
+    println!();
+
+    let mut failures = 0;
+    for filename in files {
+        let expected = remote
+            .iter()
+            .find(|f| &f.path == filename)
+            .and_then(|f| f.lfs.as_ref())
+            .map(|lfs| lfs.oid.clone());
+
+        let Some(expected) = expected else {
+            println!(
+                "  {} {} {}",
+                "?".bright_yellow(),
+                filename.bright_white(),
+                "no checksum published".bright_black()
+            );
+            continue;
+        };
+
+        let actual = sha256_file(&model_dir.join(filename))?;
+        if expected.eq_ignore_ascii_case(&actual) {
+            println!("  {} {}", "✓".bright_green(), filename.bright_white());
+        } else {
+            failures += 1;
+            println!("  {} {} {}", "✗".bright_red(), filename.bright_white(), "checksum mismatch".bright_red());
+            println!("      expected: {}", expected.bright_black());
+            println!("      actual:   {}", actual.bright_black());
+        }
+    }
+
+    println!();
+    if failures > 0 {
+        anyhow::bail!(
+            "{} file(s) failed verification. Remove them and download again.",
+            failures
+        );
+    }
+
+    println!("{} All checked files match Hugging Face.", "✓".bright_green().bold());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{serve, Response};
+
+    #[tokio::test]
+    async fn fails_on_checksum_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("good.gguf"), b"gguf").unwrap();
+        std::fs::write(dir.path().join("bad.gguf"), b"gguf").unwrap();
+        let tree = format!(
+            r#"[
+                {{"type":"file","path":"good.gguf","lfs":{{"oid":"{}","size":4}}}},
+                {{"type":"file","path":"bad.gguf","lfs":{{"oid":"0badc0de","size":4}}}}
+            ]"#,
+            sha256_file(&dir.path().join("good.gguf")).unwrap()
+        );
+        let hf = HfClient::with_endpoint(&serve(move |_| Response::ok(tree.clone())).await, None);
+
+        verify_files(&hf, "org/repo", dir.path(), &["good.gguf".to_string()]).await.unwrap();
+
+        let files = ["bad.gguf".to_string(), "good.gguf".to_string()];
+        let err = verify_files(&hf, "org/repo", dir.path(), &files).await.unwrap_err().to_string();
+        assert!(err.contains("1 file(s) failed"), "{}", err);
+    }
+}
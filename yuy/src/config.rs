@@ -1,98 +1,821 @@
 use anyhow::{Context, Result};
+use colored::Colorize;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use crate::backend::RuntimeOptions;
+use crate::preset::Preset;
+use crate::settings::{Setting, Source, RUNTIMES};
+use crate::utils::did_you_mean;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a config key holds, which decides how `yuy config set` parses it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ValueKind {
+    Text,
+    Integer,
+    Number,
+    /// Comma-separated on the command line
+    List,
+    /// `[models.<name>]`, `[presets.<name>]` and `[profiles.<name>]`
+    Table,
+}
+
+use ValueKind::*;
+
+const KEYS: &[(&str, ValueKind)] = &[
+    ("hf_token", Text),
+    ("default_runtime", Text),
+    ("runtimes", List),
+    ("default_quant", Text),
+    ("default_model", Text),
+    ("model_roots", List),
+    ("download_connections", Integer),
+    ("server_timeout", Integer),
+    ("registry_url", Text),
+    ("ollama_host", Text),
+    ("models", Table),
+    ("presets", Table),
+    ("profiles", Table),
+];
+
+const MODEL_KEYS: &[(&str, ValueKind)] = &[
+    ("ctx_size", Integer),
+    ("threads", Integer),
+    ("gpu_layers", Integer),
+    ("args", List),
+];
+
+const PRESET_KEYS: &[(&str, ValueKind)] = &[
+    ("description", Text),
+    ("temperature", Number),
+    ("top_p", Number),
+    ("top_k", Integer),
+    ("min_p", Number),
+    ("repeat_penalty", Number),
+    ("seed", Integer),
+    ("ctx_size", Integer),
+    ("n_predict", Integer),
+    ("mirostat", Integer),
+    ("mirostat_tau", Number),
+    ("mirostat_eta", Number),
+];
+
+/// A dotted key such as `default_quant` or `presets.long.temperature`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigKey {
+    /// Table names down to the key; a model or preset name is one element
+    pub path: Vec<String>,
+    pub kind: ValueKind,
+}
+
+impl ConfigKey {
+    pub fn parse(key: &str) -> Result<Self> {
+        Self::from_path(&split_key(key))
+    }
+
+    /// Checks a key given as its table names, e.g. from a parsed file.
+    pub fn from_path(path: &[String]) -> Result<Self> {
+        let key = path.join(".");
+        let Some((head, rest)) = path.split_first() else {
+            anyhow::bail!("Invalid key '{}'", key);
+        };
+        let kind = lookup(KEYS, head, &key)?;
+        let fields = match head.as_str() {
+            "models" => MODEL_KEYS,
+            "presets" => PRESET_KEYS,
+            // A profile holds any other key
+            "profiles" if rest.len() > 1 => {
+                if rest[1] == "profiles" {
+                    anyhow::bail!("Profiles can't contain other profiles ('{}')", key);
+                }
+                let inner = Self::from_path(&rest[1..]).map_err(|e| anyhow::anyhow!("profiles.{}: {}", rest[0], e))?;
+                return Ok(Self { path: path.to_vec(), kind: inner.kind });
+            }
+            "profiles" => return Ok(Self { path: path.to_vec(), kind }),
+            _ if !rest.is_empty() => anyhow::bail!("'{}' has no sub-keys; use '{}'", key, head),
+            _ => return Ok(Self { path: path.to_vec(), kind }),
+        };
+        let kind = match rest {
+            [] | [_] => Table,
+            [_, field] => lookup(fields, field, &key)?,
+            _ => anyhow::bail!("Invalid key '{}'", key),
+        };
+        Ok(Self { path: path.to_vec(), kind })
+    }
+}
+
+/// Splits a dotted key into table names. Model names may contain dots,
+/// field and profile names never do.
+fn split_key(key: &str) -> Vec<String> {
+    match key.split_once('.') {
+        None => vec![key.to_string()],
+        Some(("profiles", rest)) => {
+            let (name, inner) = rest.split_once('.').unwrap_or((rest, ""));
+            let mut path = vec!["profiles".to_string(), name.to_string()];
+            if !inner.is_empty() {
+                path.extend(split_key(inner));
+            }
+            path
+        }
+        Some((head @ ("models" | "presets"), rest)) => {
+            let mut path = vec![head.to_string()];
+            match rest.rsplit_once('.') {
+                Some((name, field)) => path.extend([name.to_string(), field.to_string()]),
+                None => path.push(rest.to_string()),
+            }
+            path
+        }
+        Some((head, rest)) => vec![head.to_string(), rest.to_string()],
+    }
+}
+
+/// The value at `path` in `table`, if set.
+pub fn table_get<'a>(table: &'a toml::Table, path: &[String]) -> Option<&'a toml::Value> {
+    let (first, rest) = path.split_first()?;
+    let value = table.get(first)?;
+    if rest.is_empty() {
+        return Some(value);
+    }
+    table_get(value.as_table()?, rest)
+}
+
+/// Lays `overrides` over `base`, table by table; anything else is replaced.
+pub fn overlay(base: &mut toml::Table, overrides: &toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => overlay(base, overrides),
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for ConfigKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.path.join("."))
+    }
+}
+
+/// Rejects unknown keys anywhere in `table`.
+fn check_keys(table: &toml::Table, path: &mut Vec<String>) -> Result<()> {
+    for (key, value) in table {
+        path.push(key.clone());
+        let checked = ConfigKey::from_path(path)?;
+        if let (Table, toml::Value::Table(child)) = (checked.kind, value) {
+            check_keys(child, path)?;
+        }
+        path.pop();
+    }
+    Ok(())
+}
+
+fn lookup(keys: &[(&str, ValueKind)], name: &str, key: &str) -> Result<ValueKind> {
+    if let Some((_, kind)) = keys.iter().find(|(k, _)| *k == name) {
+        return Ok(*kind);
+    }
+    match did_you_mean(name, keys.iter().map(|(k, _)| *k)) {
+        Some(suggestion) => anyhow::bail!("Unknown config key '{}' (did you mean '{}'?)", key, suggestion),
+        None => {
+            let known: Vec<_> = keys.iter().map(|(k, _)| *k).collect();
+            anyhow::bail!("Unknown config key '{}'. Known keys: {}", key, known.join(", "))
+        }
+    }
+}
+
+/// The config file's contents. Everything is optional; `Settings` falls
+/// back to the built-in defaults for what isn't set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Config {
     pub hf_token: Option<String>,
     pub default_runtime: Option<String>,
+    /// Runtimes `yuy run` tries in order, e.g. `["llama-cpp", "ollama"]`
+    pub runtimes: Option<Vec<String>>,
     pub default_quant: Option<String>,
     pub default_model: Option<String>,
+    /// Extra directories models are stored in, searched in order before
+    /// `<home>/models`; downloads go to the first
+    pub model_roots: Option<Vec<String>>,
+    /// Concurrent range requests per download (1 = single stream)
+    pub download_connections: Option<usize>,
+    /// Seconds to wait for llama-server to load a model
+    pub server_timeout: Option<u64>,
+    /// Where `yuy registry update` fetches the model registry from
+    pub registry_url: Option<String>,
+    /// Ollama server to use when `OLLAMA_HOST` is not set
+    pub ollama_host: Option<String>,
+    /// Per-model runtime defaults as `[models.<name>]` tables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub models: BTreeMap<String, RuntimeOptions>,
+    /// User presets as `[presets.<name>]` tables
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub presets: BTreeMap<String, Preset>,
+    /// Named overrides of any of the above as `[profiles.<name>]` tables,
+    /// picked with `--profile` or `YUY_PROFILE`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, toml::Table>,
+    /// The profile applied to this config, if any
+    #[serde(skip)]
+    pub profile: Option<Setting<String>>,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            hf_token: None,
-            default_runtime: Some("llama-cpp".to_string()),
-            default_quant: Some("q4_k_m".to_string()),
-            default_model: Some("Yuuki-NxG-3B".to_string()),
+impl Config {
+    /// The `[models.<name>]` defaults for `model`, matched case-insensitively.
+    pub fn model_options(&self, model: &str) -> RuntimeOptions {
+        self.models
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(model))
+            .map(|(_, options)| options.clone())
+            .unwrap_or_default()
+    }
+
+    /// Builds a config from a parsed file, rejecting unknown keys and
+    /// values that would only fail at run time. Profiles are checked when
+    /// one is applied, so a broken one only stops the commands that use it.
+    pub fn from_table(table: toml::Table) -> Result<Self> {
+        Self::build(table)
+    }
+
+    /// `table` with `profile` (matched case-insensitively) laid over it.
+    pub fn with_profile(mut table: toml::Table, profile: &Setting<String>) -> Result<Self> {
+        let name = profile.value.as_str();
+        let profiles = table.get("profiles").and_then(|p| p.as_table()).cloned().unwrap_or_default();
+        let Some((name, overrides)) = profiles.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)) else {
+            let hint = match did_you_mean(name, profiles.keys().map(String::as_str)) {
+                Some(suggestion) => format!(" (did you mean '{}'?)", suggestion),
+                None if profiles.is_empty() => ". Define one as [profiles.<name>] in the config".to_string(),
+                None => format!(". Profiles: {}", profiles.keys().cloned().collect::<Vec<_>>().join(", ")),
+            };
+            anyhow::bail!("Unknown profile '{}'{}", name, hint);
+        };
+        let overrides = overrides
+            .as_table()
+            .with_context(|| format!("profiles.{} must be a table", name))?;
+        overlay(&mut table, overrides);
+        let mut config = Self::build(table).map_err(|e| anyhow::anyhow!("profiles.{}: {}", name, e))?;
+        config.profile = Some(Setting::new(name.clone(), profile.source.clone()));
+        Ok(config)
+    }
+
+    fn build(table: toml::Table) -> Result<Self> {
+        let mut own = table.clone();
+        own.remove("profiles");
+        check_keys(&own, &mut Vec::new())?;
+        let config: Config = toml::Value::Table(table).try_into()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Where `key` is set: under the active profile when it overrides it.
+    pub fn origin(&self, key: &str) -> String {
+        let Some(profile) = &self.profile else {
+            return key.to_string();
+        };
+        let path = split_key(key);
+        match self.profiles.get(&profile.value).and_then(|p| table_get(p, &path)) {
+            Some(_) => format!("profiles.{}.{}", profile.value, key),
+            None => key.to_string(),
+        }
+    }
+
+    /// Checks values against the known runtimes and quantizations. Model
+    /// names need the registry and are checked by `yuy config`.
+    pub fn validate(&self) -> Result<()> {
+        let runtimes = self.runtimes.iter().flatten().map(|r| ("runtimes", r));
+        for (key, runtime) in self.default_runtime.iter().map(|r| ("default_runtime", r)).chain(runtimes) {
+            if !RUNTIMES.contains(&runtime.as_str()) {
+                let hint = did_you_mean(runtime, RUNTIMES.iter().copied())
+                    .map(|r| format!(" (did you mean '{}'?)", r))
+                    .unwrap_or_default();
+                anyhow::bail!("{}: unknown runtime '{}'{}. Use: {}", key, runtime, hint, RUNTIMES.join(", "));
+            }
+        }
+        if let Some(quant) = &self.default_quant {
+            if !crate::hf::is_quant(&quant.to_lowercase()) {
+                anyhow::bail!("default_quant: '{}' is not a quantization (e.g. q4_k_m, q8_0, f16)", quant);
+            }
+        }
+        if self.default_model.as_ref().is_some_and(|m| m.trim().is_empty()) {
+            anyhow::bail!("default_model is empty");
         }
+        for root in self.model_roots.iter().flatten() {
+            if !expand_home(root).is_absolute() {
+                anyhow::bail!("model_roots: '{}' must be an absolute path (or start with ~/)", root);
+            }
+        }
+        if self.download_connections == Some(0) {
+            anyhow::bail!("download_connections must be 1 or more");
+        }
+        if self.server_timeout == Some(0) {
+            anyhow::bail!("server_timeout must be 1 or more seconds");
+        }
+        if let Some(url) = &self.registry_url {
+            if !url.starts_with("https://") && !url.starts_with("http://") {
+                anyhow::bail!("registry_url: '{}' is not an http(s) URL", url);
+            }
+        }
+        for (model, options) in &self.models {
+            options.validate().map_err(|e| anyhow::anyhow!("[models.{}]: {}", model, e))?;
+        }
+        for (name, preset) in &self.presets {
+            Preset { name: name.clone(), ..preset.clone() }.validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Environment variable that moves every yuy file under one directory.
+pub const HOME_ENV: &str = "YUUKI_HOME";
+
+/// Directory name inside the XDG base directories.
+const XDG_NAME: &str = "yuuki";
+
+/// Set from `--home`, which beats `YUUKI_HOME`.
+static HOME_FLAG: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn set_home(dir: PathBuf) {
+    let _ = HOME_FLAG.set(dir);
+}
+
+/// Environment variable naming the profile to apply.
+pub const PROFILE_ENV: &str = "YUY_PROFILE";
+
+/// Set from `--profile`, which beats `YUY_PROFILE`.
+static PROFILE_FLAG: OnceLock<String> = OnceLock::new();
+
+pub fn set_profile(name: String) {
+    let _ = PROFILE_FLAG.set(name);
+}
+
+/// The profile to apply, from `--profile` or `YUY_PROFILE`.
+pub fn active_profile() -> Option<Setting<String>> {
+    if let Some(name) = PROFILE_FLAG.get() {
+        return Some(Setting::new(name.clone(), Source::Flag("--profile")));
     }
+    let name = std::env::var(PROFILE_ENV).ok().filter(|v| !v.trim().is_empty())?;
+    Some(Setting::new(name.trim().to_string(), Source::Env(PROFILE_ENV)))
 }
 
-pub fn get_yuuki_dir() -> Result<PathBuf> {
-    let home = dirs::home_dir().context("Could not find home directory")?;
-    let yuuki_dir = home.join(".yuuki");
+/// Where yuy keeps its files. `config` holds config.toml, registry.toml,
+/// presets and templates; `data` holds models and sessions; `cache` holds
+/// the fetched registry. All three are one directory unless the XDG
+/// layout is in use.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Layout {
+    pub config: PathBuf,
+    pub data: PathBuf,
+    pub cache: PathBuf,
+}
+
+impl Layout {
+    pub fn single(home: impl Into<PathBuf>) -> Self {
+        let home = home.into();
+        Self { config: home.clone(), data: home.clone(), cache: home }
+    }
 
-    if !yuuki_dir.exists() {
-        fs::create_dir_all(&yuuki_dir)?;
+    /// `yuuki` under `$XDG_CONFIG_HOME`, `$XDG_DATA_HOME` and `$XDG_CACHE_HOME`
+    /// (or the platform's equivalents).
+    pub fn xdg() -> Result<Self> {
+        let base = |dir: Option<PathBuf>, what: &str| {
+            dir.map(|d| d.join(XDG_NAME))
+                .with_context(|| format!("Could not find the {} directory", what))
+        };
+        Ok(Self {
+            config: base(dirs::config_dir(), "config")?,
+            data: base(dirs::data_dir(), "data")?,
+            cache: base(dirs::cache_dir(), "cache")?,
+        })
     }
 
-    Ok(yuuki_dir)
+    /// `~/.yuuki`, the layout used when nothing else is chosen.
+    pub fn legacy() -> Result<Self> {
+        let home = dirs::home_dir().context("Could not find home directory")?;
+        Ok(Self::single(home.join(".yuuki")))
+    }
+
+    pub fn is_split(&self) -> bool {
+        self.config != self.data || self.config != self.cache
+    }
+
+    /// The layout in use and what chose it: `--home`, then `YUUKI_HOME`,
+    /// then `~/.yuuki` if it exists, then the XDG layout if its config
+    /// directory exists, else `~/.yuuki`.
+    pub fn current() -> Result<Setting<Layout>> {
+        let env = std::env::var_os(HOME_ENV).filter(|v| !v.is_empty()).map(PathBuf::from);
+        let xdg = Self::xdg().ok();
+        Ok(Self::resolve(HOME_FLAG.get().cloned(), env, Self::legacy()?, xdg))
+    }
+
+    fn resolve(flag: Option<PathBuf>, env: Option<PathBuf>, legacy: Layout, xdg: Option<Layout>) -> Setting<Layout> {
+        let absolute = |dir: PathBuf| std::path::absolute(&dir).unwrap_or(dir);
+        if let Some(dir) = flag {
+            return Setting::new(Self::single(absolute(dir)), Source::Flag("--home"));
+        }
+        if let Some(dir) = env {
+            return Setting::new(Self::single(absolute(dir)), Source::Env(HOME_ENV));
+        }
+        match xdg {
+            Some(xdg) if !legacy.config.exists() && xdg.config.exists() => {
+                let reason = format!("{} exists", xdg.config.display());
+                Setting::new(xdg, Source::Auto(reason))
+            }
+            _ => Setting::new(legacy, Source::Default),
+        }
+    }
 }
 
+/// Holds config.toml, registry.toml, presets/ and templates/.
+pub fn get_config_dir() -> Result<PathBuf> {
+    Ok(Layout::current()?.value.config)
+}
+
+/// Holds models/ and sessions/.
+pub fn get_data_dir() -> Result<PathBuf> {
+    Ok(Layout::current()?.value.data)
+}
+
+/// Holds files that can be fetched again, like the registry cache.
+pub fn get_cache_dir() -> Result<PathBuf> {
+    Ok(Layout::current()?.value.cache)
+}
+
+/// Where models are stored, in search order: `model_roots` from the
+/// config, then `<data>/models`. None is created until something is
+/// downloaded into it.
+pub fn model_roots() -> Result<Vec<PathBuf>> {
+    model_roots_in(&load_config()?)
+}
+
+/// `model_roots` for `config`.
+pub fn model_roots_in(config: &Config) -> Result<Vec<PathBuf>> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    for root in config.model_roots.iter().flatten().map(|r| expand_home(r)) {
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+    let default = get_data_dir()?.join("models");
+    if !roots.contains(&default) {
+        roots.push(default);
+    }
+    Ok(roots)
+}
+
+/// The root new downloads go to.
 pub fn get_models_dir() -> Result<PathBuf> {
-    let yuuki_dir = get_yuuki_dir()?;
-    let models_dir = yuuki_dir.join("models");
+    Ok(model_roots()?.remove(0))
+}
+
+/// `model`'s directory in the first root that has it, or where a download
+/// would put it.
+pub fn model_dir(model: &str) -> Result<PathBuf> {
+    Ok(locate(&model_roots()?, model))
+}
+
+fn locate(roots: &[PathBuf], model: &str) -> PathBuf {
+    let found = roots.iter().map(|root| root.join(model)).find(|dir| dir.is_dir());
+    found.unwrap_or_else(|| roots[0].join(model))
+}
+
+/// Drops the `<org>` directory of an `org/repo` model once its last repo
+/// is gone from it.
+pub fn prune_org_dir(model: &str, model_dir: &Path) -> Result<()> {
+    if let Some(parent) = model_dir.parent().filter(|_| model.contains('/')) {
+        if parent.exists() && fs::read_dir(parent)?.next().is_none() {
+            fs::remove_dir(parent)?;
+        }
+    }
+    Ok(())
+}
 
-    if !models_dir.exists() {
-        fs::create_dir_all(&models_dir)?;
+/// `~/` at the start of a configured path means the home directory.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(rest), Some(home)) => home.join(rest),
+        _ if path == "~" => dirs::home_dir().unwrap_or_else(|| PathBuf::from(path)),
+        _ => PathBuf::from(path),
     }
+}
 
-    Ok(models_dir)
+/// Downloaded models as `(name, dir)`, sorted by name, from every root.
+/// A model in several roots is listed from the first.
+///
+/// Models from arbitrary Hugging Face repos live one level deeper, in
+/// `<org>/<repo>`, and are named that way.
+pub fn local_models() -> Result<Vec<(String, PathBuf)>> {
+    let mut models: Vec<(String, PathBuf)> = Vec::new();
+    for root in model_roots()? {
+        for (name, path) in models_in(&root)? {
+            if !models.iter().any(|(n, _)| *n == name) {
+                models.push((name, path));
+            }
+        }
+    }
+    models.sort();
+    Ok(models)
+}
+
+/// The models stored directly in `root`.
+pub fn models_in(root: &Path) -> Result<Vec<(String, PathBuf)>> {
+    let mut models = Vec::new();
+    if !root.exists() {
+        return Ok(models);
+    }
+
+    for entry in fs::read_dir(root)?.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+
+        let subdirs: Vec<_> = fs::read_dir(&path)?
+            .flatten()
+            .filter(|e| e.path().is_dir())
+            .collect();
+        let has_files = fs::read_dir(&path)?.flatten().any(|e| e.path().is_file());
+
+        if subdirs.is_empty() || has_files {
+            models.push((name, path));
+        } else {
+            for sub in subdirs {
+                let repo = sub.file_name().to_string_lossy().to_string();
+                models.push((format!("{}/{}", name, repo), sub.path()));
+            }
+        }
+    }
+
+    models.sort();
+    Ok(models)
 }
 
 pub fn get_config_path() -> Result<PathBuf> {
-    let yuuki_dir = get_yuuki_dir()?;
-    Ok(yuuki_dir.join("config.toml"))
+    Ok(get_config_dir()?.join("config.toml"))
 }
 
+/// The config with the active profile applied.
 pub fn load_config() -> Result<Config> {
+    let table = read_config_table()?;
+    let base = Config::from_table(table.clone()).map_err(invalid_config)?;
+    let Some(profile) = active_profile() else {
+        return Ok(base);
+    };
+    Config::with_profile(table, &profile)
+}
+
+/// One setting from the config, for lookups that have a fallback. An
+/// invalid config is reported on stderr rather than silently skipped.
+pub fn config_value<T>(key: &str, get: impl FnOnce(Config) -> Option<T>) -> Option<T> {
+    match load_config() {
+        Ok(config) => get(config),
+        Err(e) => {
+            eprintln!("{} Not using {} from the config: {:#}", "⚠".bright_yellow(), key, e);
+            None
+        }
+    }
+}
+
+/// The config as written, for commands that save it back.
+pub fn load_base_config() -> Result<Config> {
+    Config::from_table(read_config_table()?).map_err(invalid_config)
+}
+
+/// The config file as a table, empty when there is none yet. Nothing is
+/// created.
+fn read_config_table() -> Result<toml::Table> {
     let config_path = get_config_path()?;
+    let content = match fs::read_to_string(&config_path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(toml::Table::new()),
+        Err(e) => return Err(e).with_context(|| format!("Failed to read {}", config_path.display())),
+    };
+    parse_config_file(&config_path, &content).map_err(invalid_config)
+}
 
-    if !config_path.exists() {
-        let config = Config::default();
-        save_config(&config)?;
-        return Ok(config);
+/// Defaults that versions before `default_quant` was honored wrote into
+/// every new config file.
+const LEGACY_DEFAULTS: &[(&str, &str)] = &[
+    ("default_runtime", "llama-cpp"),
+    ("default_quant", "q4_k_m"),
+    ("default_model", "Yuuki-NxG-3B"),
+];
+
+/// Parses the config file at `path`. A file that still holds exactly
+/// `LEGACY_DEFAULTS` is rewritten without them, once, with a notice: the
+/// runtime and model are the built-in defaults anyway, and the quant
+/// would otherwise pin every older install to q4_k_m.
+pub fn parse_config_file(path: &Path, content: &str) -> Result<toml::Table> {
+    let mut table: toml::Table = toml::from_str(content)?;
+    let legacy = LEGACY_DEFAULTS
+        .iter()
+        .all(|(key, value)| table.get(*key).and_then(|v| v.as_str()) == Some(value));
+    if !legacy {
+        return Ok(table);
     }
 
-    let content = fs::read_to_string(config_path)?;
-    let config: Config = toml::from_str(&content)?;
-    Ok(config)
+    for (key, _) in LEGACY_DEFAULTS {
+        table.remove(*key);
+    }
+    match fs::write(path, toml::to_string_pretty(&table)?) {
+        Ok(()) => eprintln!(
+            "{} Removed the defaults older versions wrote to {}, so the quantization is picked for your hardware. \
+             To keep q4_k_m: yuy config set default_quant q4_k_m",
+            "ℹ".bright_blue(),
+            path.display()
+        ),
+        Err(e) => eprintln!(
+            "{} Ignoring default_quant = \"q4_k_m\" that older versions wrote to {}; could not update the file: {}",
+            "⚠".bright_yellow(),
+            path.display(),
+            e
+        ),
+    }
+    Ok(table)
+}
+
+fn invalid_config(e: anyhow::Error) -> anyhow::Error {
+    let path = get_config_path().map(|p| p.display().to_string()).unwrap_or_default();
+    anyhow::anyhow!(
+        "Invalid config {}: {}. Fix it with: yuy config edit",
+        path,
+        e.to_string().trim_end()
+    )
 }
 
 pub fn save_config(config: &Config) -> Result<()> {
     let config_path = get_config_path()?;
     let content = toml::to_string_pretty(config)?;
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
     fs::write(config_path, content)?;
     Ok(())
 }
 
-// Modelos oficiales OpceanAI en HuggingFace
-pub const YUUKI_MODELS: &[(&str, &str)] = &[
-    ("Yuuki-NxG-vl",   "OpceanAI/Yuuki-NxG-vl"),   // 7B vision+text
-    ("Yuuki-NxG-3B",   "OpceanAI/Yuuki-NxG"),       // 3B conversacional bilingüe
-    ("Yuuki-NxG-Nano", "OpceanAI/Yuuki-NxG-Nano"),  // 81M ligero
-];
+pub const HF_ORG: &str = "OpceanAI";
+pub const OLLAMA_ORG: &str = "aguitachan3";
+#[allow(dead_code)]
+pub const YUUKI_API: &str = "https://huggingface.co/spaces/OpceanAI/Yuuki-api";
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-// Modelos cuantizados GGUF disponibles
-pub const YUUKI_QUANTIZED_MODELS: &[(&str, &str)] = &[
-    ("Yuuki-NxG-vl", "mradermacher/Yuuki-NxG-vl-GGUF"),
-];
+    fn parse(text: &str) -> Result<Config> {
+        Config::from_table(toml::from_str(text)?)
+    }
 
-// Cuantizaciones disponibles
-pub const AVAILABLE_QUANTS: &[&str] = &[
-    "q2_k",   // 3.02 GB — mínimo
-    "q3_k_m", // 3.81 GB — ligero
-    "q4_k_m", // 4.68 GB — recomendado
-    "q5_k_m", // 5.44 GB — calidad alta
-    "q6_k",   // 6.25 GB — casi full
-    "q8_0",   // 8.10 GB — máximo cuantizado
-    "f16",    // 15.2 GB — full precision
-];
+    #[test]
+    fn picks_the_home_layout() {
+        let root = tempfile::tempdir().unwrap();
+        let legacy = Layout::single(root.path().join(".yuuki"));
+        let xdg = Layout {
+            config: root.path().join("config/yuuki"),
+            data: root.path().join("share/yuuki"),
+            cache: root.path().join("cache/yuuki"),
+        };
+        let resolve = |flag: Option<&str>, env: Option<&str>| {
+            Layout::resolve(flag.map(PathBuf::from), env.map(PathBuf::from), legacy.clone(), Some(xdg.clone()))
+        };
 
-pub const HF_ORG: &str = "OpceanAI";
-pub const OLLAMA_ORG: &str = "aguitachan3";
-pub const YUUKI_API: &str = "https://huggingface.co/spaces/OpceanAI/Yuuki-api";
\ No newline at end of file
+        assert_eq!(resolve(Some("/a"), Some("/b")), Setting::new(Layout::single("/a"), Source::Flag("--home")));
+        assert_eq!(resolve(None, Some("/b")).source, Source::Env(HOME_ENV));
+        assert_eq!(resolve(None, None), Setting::new(legacy.clone(), Source::Default));
+
+        fs::create_dir_all(&xdg.config).unwrap();
+        let picked = resolve(None, None);
+        assert_eq!(picked.value, xdg);
+        assert!(picked.value.is_split() && matches!(picked.source, Source::Auto(_)));
+        // An existing ~/.yuuki wins, so nothing moves until migrate-home
+        fs::create_dir_all(&legacy.config).unwrap();
+        assert_eq!(resolve(None, None).value, legacy);
+    }
+
+    #[test]
+    fn finds_models_in_any_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let roots = [dir.path().join("nvme"), dir.path().join("hdd")];
+        fs::create_dir_all(roots[1].join("Yuuki-3.7")).unwrap();
+        fs::create_dir_all(roots[1].join("org/repo-GGUF")).unwrap();
+        fs::create_dir_all(roots[0].join("Yuuki-best")).unwrap();
+
+        assert_eq!(locate(&roots, "Yuuki-3.7"), roots[1].join("Yuuki-3.7"));
+        assert_eq!(locate(&roots, "Yuuki-v0.1"), roots[0].join("Yuuki-v0.1"));
+        let names: Vec<_> = models_in(&roots[1]).unwrap().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["Yuuki-3.7", "org/repo-GGUF"]);
+        assert!(models_in(&dir.path().join("missing")).unwrap().is_empty());
+
+        assert!(parse("model_roots = [\"~/models\", \"/mnt/hdd\"]\n").is_ok());
+        assert!(parse("model_roots = [\"models\"]\n").is_err());
+    }
+
+    #[test]
+    fn migrates_the_legacy_defaults_once() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        // What every config file looked like before default_quant was honored
+        let baseline = "default_runtime = \"llama-cpp\"\ndefault_quant = \"q4_k_m\"\ndefault_model = \"Yuuki-NxG-3B\"\n";
+        let legacy = format!("hf_token = \"hf_abc\"\n{}", baseline);
+        fs::write(&path, &legacy).unwrap();
+
+        let config = Config::from_table(parse_config_file(&path, &legacy).unwrap()).unwrap();
+        assert_eq!((config.default_quant, config.default_model, config.default_runtime), (None, None, None));
+        assert_eq!(config.hf_token.as_deref(), Some("hf_abc"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hf_token = \"hf_abc\"\n");
+
+        // Chosen on purpose once anything else differs; the file is left alone
+        for chosen in [baseline.replace("Yuuki-NxG-3B", "Yuuki-NxG-vl"), baseline.replace("q4_k_m", "q8_0")] {
+            let table = parse_config_file(&path, &chosen).unwrap();
+            assert_eq!(table.len(), 3);
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "hf_token = \"hf_abc\"\n");
+    }
+
+    #[test]
+    fn applies_profiles_over_the_file() {
+        let text = r#"
+            default_quant = "q8_0"
+            runtimes = ["llama-cpp"]
+            [models.Yuuki-best]
+            ctx_size = 16384
+            threads = 12
+            [profiles.laptop]
+            default_quant = "q4_k_m"
+            runtimes = ["ollama"]
+            [profiles.laptop.models.Yuuki-best]
+            ctx_size = 4096
+            [profiles.laptop.presets.balanced]
+            temperature = 0.5
+        "#;
+        let table: toml::Table = toml::from_str(text).unwrap();
+        let base = Config::from_table(table.clone()).unwrap();
+        assert_eq!(base.default_quant.as_deref(), Some("q8_0"));
+        assert_eq!(base.origin("default_quant"), "default_quant");
+
+        let profile = Setting::new("Laptop".to_string(), Source::Env(PROFILE_ENV));
+        let laptop = Config::with_profile(table.clone(), &profile).unwrap();
+        assert_eq!(laptop.default_quant.as_deref(), Some("q4_k_m"));
+        assert_eq!(laptop.runtimes, Some(vec!["ollama".to_string()]));
+        let options = laptop.model_options("Yuuki-best");
+        assert_eq!((options.ctx_size, options.threads), (Some(4096), Some(12)));
+        assert_eq!(laptop.presets["balanced"].temperature, Some(0.5));
+        assert_eq!(laptop.profile, Some(Setting::new("laptop".to_string(), Source::Env(PROFILE_ENV))));
+        assert_eq!(laptop.origin("models.Yuuki-best.ctx_size"), "profiles.laptop.models.Yuuki-best.ctx_size");
+        assert_eq!(laptop.origin("models.Yuuki-best.threads"), "models.Yuuki-best.threads");
+
+        let desk = Setting::new("lapto".to_string(), Source::Flag("--profile"));
+        let err = Config::with_profile(table, &desk).unwrap_err().to_string();
+        assert!(err.contains("did you mean 'laptop'"), "{}", err);
+
+        // A broken profile only fails once it is applied
+        let text = "default_quant = \"q8_0\"\n[profiles.desk]\ndefault_runtime = \"vllm\"\n[profiles.travel]\ndefault_quantt = \"q8_0\"\n";
+        let table: toml::Table = toml::from_str(text).unwrap();
+        assert_eq!(parse(text).unwrap().default_quant.as_deref(), Some("q8_0"));
+        let apply = |name: &str| {
+            let profile = Setting::new(name.to_string(), Source::Flag("--profile"));
+            Config::with_profile(table.clone(), &profile).unwrap_err().to_string()
+        };
+        let err = apply("desk");
+        assert!(err.contains("profiles.desk") && err.contains("vllm"), "{}", err);
+        assert!(apply("travel").contains("did you mean 'default_quant'"));
+
+        let key = ConfigKey::parse("profiles.laptop.models.Yuuki-3.7.args").unwrap();
+        assert_eq!(key.path, ["profiles", "laptop", "models", "Yuuki-3.7", "args"]);
+        assert_eq!(key.kind, ValueKind::List);
+        assert!(ConfigKey::parse("profiles.laptop.profiles.x").is_err());
+    }
+
+    #[test]
+    fn parses_dotted_keys() {
+        let key = ConfigKey::parse("models.Yuuki-3.7.threads").unwrap();
+        assert_eq!(key.path, ["models", "Yuuki-3.7", "threads"]);
+        assert_eq!(key.kind, ValueKind::Integer);
+        assert_eq!(ConfigKey::parse("presets.long").unwrap().kind, ValueKind::Table);
+        assert_eq!(ConfigKey::parse("runtimes").unwrap().kind, ValueKind::List);
+
+        let err = ConfigKey::parse("default_runtim").unwrap_err().to_string();
+        assert!(err.contains("did you mean 'default_runtime'"), "{}", err);
+        let err = ConfigKey::parse("presets.long.temprature").unwrap_err().to_string();
+        assert!(err.contains("did you mean 'temperature'"), "{}", err);
+        assert!(ConfigKey::parse("default_quant.x").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_keys_and_bad_values() {
+        let config = parse("default_quant = \"Q4_K_M\"\nruntimes = [\"ollama\"]\n[models.Yuuki-best]\nthreads = 4\n").unwrap();
+        assert_eq!(config.model_options("yuuki-best").threads, Some(4));
+
+        let err = parse("default_modle = \"Yuuki-best\"\n").unwrap_err().to_string();
+        assert!(err.contains("did you mean 'default_model'"), "{}", err);
+        let err = parse("[presets.long]\ntop_kk = 4\n").unwrap_err().to_string();
+        assert!(err.contains("presets.long.top_kk"), "{}", err);
+        let err = parse("default_runtime = \"lama-cpp\"\n").unwrap_err().to_string();
+        assert!(err.contains("did you mean 'llama-cpp'"), "{}", err);
+        assert!(parse("default_quant = \"fast\"\n").is_err());
+        assert!(parse("download_connections = 0\n").is_err());
+        assert!(parse("server_timeout = 0\n").is_err());
+        assert!(parse("[models.Yuuki-best]\nctx_size = 0\n").is_err());
+        assert!(parse("[presets.hot]\ntemperature = -1.0\n").is_err());
+    }
+}
@@ -0,0 +1,407 @@
+use anyhow::{Context, Result};
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, LINK};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use crate::config::config_value;
+
+pub const HF_ENDPOINT: &str = "https://huggingface.co";
+
+/// Small client for the Hugging Face Hub REST API.
+///
+/// The endpoint can be overridden with the `HF_ENDPOINT` env var, the same
+/// way `huggingface_hub` does it (mirrors, local test servers).
+#[derive(Clone)]
+pub struct HfClient {
+    http: reqwest::Client,
+    endpoint: String,
+    authenticated: bool,
+}
+
+/// An entry of `GET /api/models/{repo}/tree/main`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RepoFile {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub path: String,
+    #[serde(default)]
+    pub size: u64,
+    pub lfs: Option<LfsInfo>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LfsInfo {
+    /// SHA256 of the file content
+    pub oid: String,
+}
+
+/// A GGUF model file in a repo, with the quantization parsed from its name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GgufFile {
+    pub filename: String,
+    /// Lowercase quant type, e.g. `q4_k_m`
+    pub quant: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WhoAmI {
+    name: String,
+}
+
+/// Hub endpoint, honoring `HF_ENDPOINT`.
+pub fn endpoint() -> String {
+    std::env::var("HF_ENDPOINT").unwrap_or_else(|_| HF_ENDPOINT.to_string())
+}
+
+/// Token from the `HF_TOKEN` env var, or the one saved by `yuy login`.
+pub fn resolve_token() -> Option<String> {
+    std::env::var("HF_TOKEN")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .or_else(|| config_value("hf_token", |c| c.hf_token))
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+impl HfClient {
+    pub fn new() -> Self {
+        Self::with_endpoint(&endpoint(), resolve_token().as_deref())
+    }
+
+    /// Client for `endpoint`, sending `token` as a bearer token if given.
+    ///
+    /// reqwest drops the header when a download redirects to another host
+    /// (the CDN), so the token only goes to the Hub itself.
+    pub fn with_endpoint(endpoint: &str, token: Option<&str>) -> Self {
+        let mut headers = HeaderMap::new();
+        if let Some(token) = token {
+            if let Ok(mut value) = HeaderValue::from_str(&format!("Bearer {}", token)) {
+                value.set_sensitive(true);
+                headers.insert(AUTHORIZATION, value);
+            }
+        }
+
+        Self {
+            http: reqwest::Client::builder()
+                .default_headers(headers.clone())
+                .build()
+                .unwrap_or_default(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            authenticated: !headers.is_empty(),
+        }
+    }
+
+    pub fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    pub fn resolve_url(&self, repo: &str, filename: &str) -> String {
+        format!("{}/{}/resolve/main/{}", self.endpoint, repo, filename)
+    }
+
+    /// Lists the files at the root of `repo`, following the Hub's
+    /// pagination.
+    pub async fn list_files(&self, repo: &str) -> Result<Vec<RepoFile>> {
+        let mut url = format!("{}/api/models/{}/tree/main", self.endpoint, repo);
+        let mut files = Vec::new();
+        loop {
+            let response = self
+                .http
+                .get(&url)
+                .send()
+                .await
+                .with_context(|| format!("Failed to query Hugging Face for {}", repo))?;
+
+            if let Some(e) = self.auth_error(response.status(), repo) {
+                return Err(e);
+            }
+            if !response.status().is_success() {
+                anyhow::bail!("Failed to list files of {}: HTTP {}", repo, response.status());
+            }
+
+            let next = response.headers().get(LINK).and_then(|v| v.to_str().ok()).and_then(next_page);
+            let page: Vec<RepoFile> = response
+                .json()
+                .await
+                .with_context(|| format!("Unexpected response listing {}", repo))?;
+            files.extend(page.into_iter().filter(|f| f.kind == "file"));
+
+            match next {
+                Some(next) => url = next,
+                None => return Ok(files),
+            }
+        }
+    }
+
+    /// Name of the account `token` belongs to, as reported by `whoami-v2`.
+    pub async fn whoami(&self) -> Result<String> {
+        let url = format!("{}/api/whoami-v2", self.endpoint);
+        let response = self
+            .http
+            .get(&url)
+            .send()
+            .await
+            .context("Failed to reach Hugging Face")?;
+
+        if response.status() == StatusCode::UNAUTHORIZED {
+            anyhow::bail!("Hugging Face rejected the token (HTTP 401). Check that it is valid and not revoked.");
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to validate token: HTTP {}", response.status());
+        }
+
+        let whoami: WhoAmI = response.json().await.context("Unexpected whoami response")?;
+        Ok(whoami.name)
+    }
+
+    /// Actionable error for 401/403 responses about `target`.
+    pub fn auth_error(&self, status: StatusCode, target: &str) -> Option<anyhow::Error> {
+        match status {
+            StatusCode::UNAUTHORIZED if self.authenticated => Some(anyhow::anyhow!(
+                "Hugging Face rejected your token for {} (HTTP 401). \
+                 It may be revoked or expired: run 'yuy login' again or update HF_TOKEN.",
+                target
+            )),
+            StatusCode::UNAUTHORIZED => Some(anyhow::anyhow!(
+                "{} requires authentication (HTTP 401). The repo is gated, private or doesn't exist. \
+                 Run 'yuy login' or set HF_TOKEN, then try again.",
+                target
+            )),
+            StatusCode::FORBIDDEN => Some(anyhow::anyhow!(
+                "Access to {} was denied (HTTP 403). For gated models, accept the terms on the model page \
+                 with the account your token belongs to, and make sure the token has read access.",
+                target
+            )),
+            _ => None,
+        }
+    }
+
+    /// Lists the GGUF model files of `repo`, sorted by size.
+    ///
+    /// Vision projector (`mmproj`) files and files without a recognizable
+    /// quant type in their name are skipped.
+    pub async fn list_gguf_files(&self, repo: &str) -> Result<Vec<GgufFile>> {
+        let mut ggufs: Vec<GgufFile> = self
+            .list_files(repo)
+            .await?
+            .into_iter()
+            .filter(|f| !f.path.to_lowercase().contains("mmproj"))
+            .filter_map(|f| {
+                let quant = parse_quant(&f.path)?;
+                Some(GgufFile {
+                    filename: f.path,
+                    quant,
+                    size: f.size,
+                })
+            })
+            .collect();
+        ggufs.sort_by_key(|f| f.size);
+        Ok(ggufs)
+    }
+
+    /// Looks up `paths` in `repo`, wherever they are in the tree. Paths
+    /// that don't exist are left out of the result.
+    pub async fn paths_info(&self, repo: &str, paths: &[String]) -> Result<Vec<RepoFile>> {
+        let url = format!("{}/api/models/{}/paths-info/main", self.endpoint, repo);
+        let response = self
+            .http
+            .post(&url)
+            .json(&serde_json::json!({ "paths": paths, "expand": false }))
+            .send()
+            .await
+            .with_context(|| format!("Failed to query Hugging Face for {}", repo))?;
+
+        if let Some(e) = self.auth_error(response.status(), repo) {
+            return Err(e);
+        }
+        if !response.status().is_success() {
+            anyhow::bail!("Failed to look up files of {}: HTTP {}", repo, response.status());
+        }
+
+        let files: Vec<RepoFile> = response
+            .json()
+            .await
+            .with_context(|| format!("Unexpected response looking up files of {}", repo))?;
+        Ok(files.into_iter().filter(|f| f.kind == "file").collect())
+    }
+
+    /// SHA256 that Hugging Face stores for an LFS file, if it is one.
+    pub async fn lfs_sha256(&self, repo: &str, filename: &str) -> Result<Option<String>> {
+        let files = self.paths_info(repo, &[filename.to_string()]).await?;
+        Ok(files
+            .into_iter()
+            .find(|f| f.path == filename)
+            .and_then(|f| f.lfs)
+            .map(|lfs| lfs.oid))
+    }
+}
+
+/// URL of the next page from a `Link` header, e.g.
+/// `<https://huggingface.co/api/models/x/tree/main?cursor=abc>; rel="next"`.
+fn next_page(link: &str) -> Option<String> {
+    link.split(',').find_map(|part| {
+        let (url, params) = part.split_once(';')?;
+        params
+            .split(';')
+            .any(|p| p.trim() == "rel=\"next\"")
+            .then(|| url.trim().trim_start_matches('<').trim_end_matches('>').to_string())
+    })
+}
+
+/// Finds the file for `quant` (case-insensitive) among `files`.
+pub fn pick_quant<'a>(files: &'a [GgufFile], quant: &str) -> Option<&'a GgufFile> {
+    let quant = quant.to_lowercase();
+    files.iter().find(|f| f.quant == quant)
+}
+
+/// Extracts the quant type from a GGUF filename, lowercased.
+///
+/// Handles the common naming schemes: `model-q4_k_m.gguf`,
+/// `Model.Q4_K_M.gguf`, `Model-IQ4_XS.gguf`, `model-f16.gguf`.
+pub fn parse_quant(filename: &str) -> Option<String> {
+    let lower = filename.to_lowercase();
+    let stem = lower.strip_suffix(".gguf")?;
+    stem.rsplit(['.', '-'])
+        .find(|token| is_quant(token))
+        .map(str::to_string)
+}
+
+/// Whether `token` (lowercase) names a quantization, e.g. `q4_k_m` or `iq3_xs`.
+pub fn is_quant(token: &str) -> bool {
+    if matches!(token, "f16" | "f32" | "bf16") {
+        return true;
+    }
+
+    let rest = token.strip_prefix('i').unwrap_or(token);
+    let Some(rest) = rest.strip_prefix('q') else {
+        return false;
+    };
+    let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+    let suffix = &rest[digits..];
+
+    digits > 0
+        && (suffix.is_empty()
+            || (suffix.starts_with('_')
+                && suffix.chars().all(|c| c == '_' || c.is_ascii_alphanumeric())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{serve, Response};
+
+    /// Serves `json` for every request, like the Hub's tree endpoint. With
+    /// `token` set, requests without that bearer token get a 401.
+    async fn mock_api(json: &'static str, token: Option<&'static str>) -> String {
+        serve(move |request| {
+            let authorized = token.is_none_or(|t| request.header("authorization") == Some(&format!("Bearer {}", t)));
+            if authorized {
+                Response::ok(json).header("Content-Type", "application/json")
+            } else {
+                Response::new("401 Unauthorized", Vec::new())
+            }
+        })
+        .await
+    }
+
+    // Trimmed recording of GET /api/models/mradermacher/Yuuki-NxG-vl-GGUF/tree/main
+    const TREE: &str = r#"[
+        {"type":"file","oid":"a1","size":1519,"path":".gitattributes"},
+        {"type":"file","oid":"b2","size":1010,"path":"README.md"},
+        {"type":"file","oid":"c3","size":8098525184,"path":"Yuuki-NxG-vl.Q8_0.gguf","lfs":{"oid":"8f1e0c0d3a7e","size":8098525184,"pointerSize":135}},
+        {"type":"file","oid":"d4","size":3015940096,"path":"Yuuki-NxG-vl.Q2_K.gguf","lfs":{"oid":"2b7d19a4c6f0","size":3015940096,"pointerSize":135}},
+        {"type":"file","oid":"e5","size":4683073536,"path":"Yuuki-NxG-vl.Q4_K_M.gguf","lfs":{"oid":"4c8a7e3b1d25","size":4683073536,"pointerSize":135}},
+        {"type":"file","oid":"f6","size":1354163552,"path":"Yuuki-NxG-vl.mmproj-f16.gguf","lfs":{"oid":"9e0f1a2b3c4d","size":1354163552,"pointerSize":135}},
+        {"type":"directory","oid":"g7","size":0,"path":"imatrix"}
+    ]"#;
+
+    #[test]
+    fn parses_quant_from_common_naming_schemes() {
+        assert_eq!(parse_quant("yuuki-best-q4_0.gguf").as_deref(), Some("q4_0"));
+        assert_eq!(parse_quant("Yuuki-NxG-vl.Q4_K_M.gguf").as_deref(), Some("q4_k_m"));
+        assert_eq!(parse_quant("Yuuki-NxG-vl.IQ4_XS.gguf").as_deref(), Some("iq4_xs"));
+        assert_eq!(parse_quant("Yuuki-NxG-Nano-F16.gguf").as_deref(), Some("f16"));
+        assert_eq!(parse_quant("Yuuki-NxG-vl.Q4_K_M.gguf.part"), None);
+        assert_eq!(parse_quant("README.md"), None);
+    }
+
+    #[tokio::test]
+    async fn lists_gguf_files_from_repo_tree() {
+        let hf = HfClient::with_endpoint(&mock_api(TREE, None).await, None);
+        let files = hf.list_gguf_files("mradermacher/Yuuki-NxG-vl-GGUF").await.unwrap();
+
+        let quants: Vec<_> = files.iter().map(|f| f.quant.as_str()).collect();
+        assert_eq!(quants, ["q2_k", "q4_k_m", "q8_0"]);
+
+        let picked = pick_quant(&files, "Q4_K_M").unwrap();
+        assert_eq!(picked.filename, "Yuuki-NxG-vl.Q4_K_M.gguf");
+        assert_eq!(picked.size, 4683073536);
+        assert!(pick_quant(&files, "q5_k_m").is_none());
+    }
+
+    #[tokio::test]
+    async fn follows_tree_pagination() {
+        let base = std::sync::Arc::new(std::sync::OnceLock::<String>::new());
+        let next = base.clone();
+        let url = serve(move |request| match request.path.as_str() {
+            "/api/models/org/repo/tree/main" => Response::ok(r#"[{"type":"file","size":1,"path":"a.Q4_0.gguf"}]"#)
+                .header("Link", format!("<{}/api/models/org/repo/tree/main?cursor=2>; rel=\"next\"", next.get().unwrap())),
+            _ => Response::ok(r#"[{"type":"file","size":2,"path":"a.Q8_0.gguf"}]"#),
+        })
+        .await;
+        base.set(url.clone()).unwrap();
+
+        let files = HfClient::with_endpoint(&url, None).list_files("org/repo").await.unwrap();
+        let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["a.Q4_0.gguf", "a.Q8_0.gguf"]);
+    }
+
+    #[tokio::test]
+    async fn reads_lfs_sha256_for_file() {
+        // Answers paths-info with the requested entries, like the Hub
+        let url = serve(|request| {
+            assert_eq!((request.method.as_str(), request.path.as_str()), ("POST", "/api/models/org/repo/paths-info/main"));
+            let query: serde_json::Value = serde_json::from_slice(&request.body).unwrap();
+            let entries: Vec<&str> = query["paths"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .filter_map(|path| match path.as_str().unwrap() {
+                    "README.md" => Some(r#"{"type":"file","oid":"b2","size":1010,"path":"README.md"}"#),
+                    "Q8_0/model.Q8_0-00002-of-00002.gguf" => Some(
+                        r#"{"type":"file","oid":"c3","size":8,"path":"Q8_0/model.Q8_0-00002-of-00002.gguf","lfs":{"oid":"8f1e0c0d3a7e","size":8,"pointerSize":135}}"#,
+                    ),
+                    _ => None,
+                })
+                .collect();
+            Response::ok(format!("[{}]", entries.join(",")))
+        })
+        .await;
+        let hf = HfClient::with_endpoint(&url, None);
+
+        let sha = hf.lfs_sha256("org/repo", "Q8_0/model.Q8_0-00002-of-00002.gguf").await.unwrap();
+        assert_eq!(sha.as_deref(), Some("8f1e0c0d3a7e"));
+        assert_eq!(hf.lfs_sha256("org/repo", "README.md").await.unwrap(), None);
+        assert_eq!(hf.lfs_sha256("org/repo", "missing.gguf").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn sends_token_as_bearer_header() {
+        let url = mock_api(r#"{"type":"user","name":"aguitachan3"}"#, Some("hf_good")).await;
+
+        let hf = HfClient::with_endpoint(&url, Some("hf_good"));
+        assert_eq!(hf.whoami().await.unwrap(), "aguitachan3");
+
+        let hf = HfClient::with_endpoint(&url, Some("hf_bad"));
+        assert!(hf.whoami().await.unwrap_err().to_string().contains("rejected"));
+    }
+
+    #[tokio::test]
+    async fn maps_401_to_login_hint() {
+        let url = mock_api(TREE, Some("hf_good")).await;
+        let hf = HfClient::with_endpoint(&url, None);
+
+        let err = hf.list_files("OpceanAI/private-model").await.unwrap_err();
+        assert!(err.to_string().contains("yuy login"));
+    }
+}
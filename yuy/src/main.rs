@@ -1,8 +1,20 @@
+mod backend;
 mod cli;
 mod commands;
 mod config;
+mod hf;
+mod ollama;
+mod preset;
+mod registry;
+mod server;
+mod settings;
+mod session;
+mod template;
+#[cfg(test)]
+mod test_support;
 mod utils;
 
+use backend::RuntimeOptions;
 use clap::Parser;
 use cli::{Cli, Commands};
 use colored::Colorize;
@@ -10,13 +22,25 @@ use colored::Colorize;
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    if let Some(home) = cli.home {
+        config::set_home(home);
+    }
+    if let Some(profile) = cli.profile {
+        config::set_profile(profile);
+    }
 
     let result = match cli.command {
-        Some(Commands::Download { model, quant }) => {
-            commands::download::execute(&model, quant).await
+        Some(Commands::Download { model, quant, connections, explain }) => {
+            commands::download::execute(model, quant, connections, explain).await
+        }
+        Some(Commands::Run { model, runtime, quant, preset, resume, session, template, vars, prompt, ctx, threads, gpu_layers, args, explain }) => {
+            let load = RuntimeOptions { ctx_size: ctx, threads, gpu_layers, args };
+            let options = commands::run::RunOptions { runtime, quant, preset, resume, session, template, vars, prompt, load, explain };
+            commands::run::execute(model, options).await
         }
-        Some(Commands::Run { model, runtime, quant, preset, resume, template }) => {
-            commands::run::execute(&model, runtime, quant, preset, resume, template).await
+        Some(Commands::Batch { model, input, output, runtime, quant, preset, template, vars, concurrency }) => {
+            let options = commands::batch::BatchOptions { input, output, runtime, quant, preset, template, vars, concurrency };
+            commands::batch::execute(model, options).await
         }
         Some(Commands::List { target }) => {
             commands::list::execute(target).await
@@ -27,12 +51,45 @@ async fn main() {
         Some(Commands::Remove { model }) => {
             commands::remove::execute(&model).await
         }
+        Some(Commands::Verify { model }) => {
+            commands::verify::execute(&model).await
+        }
+        Some(Commands::Sessions { action }) => {
+            commands::sessions::execute(action).await
+        }
+        Some(Commands::Models { action }) => {
+            commands::models::execute(action).await
+        }
+        Some(Commands::Template { action }) => {
+            commands::template::execute(action).await
+        }
+        Some(Commands::Preset { action }) => {
+            commands::preset::execute(action).await
+        }
         Some(Commands::Runtime { action }) => {
             commands::runtime::execute(action).await
         }
+        Some(Commands::Ollama { action }) => {
+            commands::ollama::execute(action).await
+        }
+        Some(Commands::Registry { action }) => {
+            commands::registry::execute(action).await
+        }
+        Some(Commands::Config { action }) => {
+            commands::config::execute(action).await
+        }
+        Some(Commands::Login { token }) => {
+            commands::login::execute(token).await
+        }
+        Some(Commands::Logout) => {
+            commands::logout::execute().await
+        }
         Some(Commands::Doctor) => {
             commands::doctor::execute().await
         }
+        Some(Commands::MigrateHome { to, xdg, dry_run }) => {
+            commands::migrate_home::execute(to, xdg, dry_run).await
+        }
         Some(Commands::Setup) => {
             commands::setup::execute().await
         }
@@ -44,9 +101,27 @@ async fn main() {
     };
 
     if let Err(e) = result {
-        eprintln!("{} {}", "Error:".bright_red().bold(), e);
-        std::process::exit(1);
+        // Ctrl-C needs no explanation
+        if e.downcast_ref::<backend::Interrupted>().is_none() {
+            eprintln!("{} {}", "Error:".bright_red().bold(), e);
+        }
+        std::process::exit(exit_code(&e));
+    }
+}
+
+/// Status to exit with after `e`. A failing runtime's own status is passed
+/// through to scripts.
+fn exit_code(e: &anyhow::Error) -> i32 {
+    if e.downcast_ref::<backend::NoRuntime>().is_some() {
+        return backend::NO_RUNTIME_EXIT_CODE;
     }
+    if e.downcast_ref::<backend::Interrupted>().is_some() {
+        return backend::INTERRUPTED_EXIT_CODE;
+    }
+    e.downcast_ref::<backend::RuntimeExit>()
+        .and_then(|exit| exit.code)
+        .filter(|&code| code != 0)
+        .unwrap_or(1)
 }
 
 fn print_banner() {
@@ -74,3 +149,23 @@ $$\     $$\
             .bold()
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use backend::{Interrupted, NoRuntime, RuntimeExit, RuntimeUnavailable};
+
+    #[test]
+    fn maps_errors_to_exit_codes() {
+        let unavailable = RuntimeUnavailable { runtime: "ollama".into(), reason: "not running".into() };
+        let none = anyhow::Error::from(NoRuntime { model: "Yuuki-best".into(), tried: vec![unavailable] });
+        assert_eq!(exit_code(&none), 127);
+        assert_eq!(exit_code(&Interrupted.into()), 130);
+
+        let exit = |code| anyhow::Error::from(RuntimeExit { runtime: "llama-cli".into(), code, detail: String::new() });
+        assert_eq!(exit_code(&exit(Some(3))), 3);
+        assert_eq!(exit_code(&exit(Some(0))), 1);
+        assert_eq!(exit_code(&exit(None)), 1);
+        assert_eq!(exit_code(&anyhow::anyhow!("Model 'x' not found")), 1);
+    }
+}
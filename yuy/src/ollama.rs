@@ -0,0 +1,594 @@
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::AsyncReadExt;
+use crate::backend::{ChatFormat, Completion, RuntimeOptions};
+use crate::config::config_value;
+use crate::preset::Preset;
+use crate::session::Message;
+use crate::utils::DEFAULT_CONTEXT;
+
+/// Where `ollama serve` listens by default.
+pub const OLLAMA_HOST: &str = "http://127.0.0.1:11434";
+
+/// Small client for the Ollama REST API.
+///
+/// The server is taken from `OLLAMA_HOST`, like the `ollama` CLI does, then
+/// `ollama_host` in the config.
+#[derive(Debug, Clone)]
+pub struct OllamaClient {
+    http: reqwest::Client,
+    host: String,
+}
+
+/// An entry of `GET /api/tags`.
+#[derive(Debug, Deserialize)]
+struct LocalModel {
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tags {
+    #[serde(default)]
+    models: Vec<LocalModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Version {
+    version: String,
+}
+
+/// One progress line of `POST /api/pull`. Layer downloads carry a digest
+/// and byte counts; other steps only a status.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PullProgress {
+    #[serde(default)]
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+/// An Ollama model built from a local GGUF file, with the chat template,
+/// system prompt and preset baked in.
+#[derive(Debug, Clone)]
+pub struct Modelfile {
+    pub gguf: PathBuf,
+    pub format: ChatFormat,
+    pub system: Option<String>,
+    pub preset: Preset,
+    pub options: RuntimeOptions,
+}
+
+impl Modelfile {
+    /// Text for `ollama create -f`.
+    pub fn render(&self) -> Result<String> {
+        let mut text = String::from("# Generated by yuy ollama import\n");
+        text.push_str(&format!("FROM {}\n", self.gguf.display()));
+        if let Some(template) = self.format.ollama_template() {
+            text.push_str(&format!("TEMPLATE \"\"\"{}\"\"\"\n", template));
+        }
+        if let Some(system) = &self.system {
+            if system.contains("\"\"\"") {
+                anyhow::bail!("The system prompt can't contain \"\"\" in a Modelfile");
+            }
+            text.push_str(&format!("SYSTEM \"\"\"{}\"\"\"\n", system));
+        }
+        for (name, value) in self.parameters()? {
+            match value {
+                Value::Array(values) => {
+                    for value in values {
+                        text.push_str(&format!("PARAMETER {} {}\n", name, value));
+                    }
+                }
+                value => text.push_str(&format!("PARAMETER {} {}\n", name, value)),
+            }
+        }
+        Ok(text)
+    }
+
+    fn parameters(&self) -> Result<Map<String, Value>> {
+        let mut parameters = model_options(&self.preset, &self.options)?;
+        let stop = self.format.stop_tokens();
+        if !stop.is_empty() {
+            parameters.insert("stop".to_string(), Value::from(stop.to_vec()));
+        }
+        Ok(parameters)
+    }
+
+    /// `/api/create` body, with the GGUF already uploaded as blob `digest`.
+    fn create_request(&self, name: &str, digest: &str) -> Result<Value> {
+        let filename = self.gguf.file_name().unwrap_or_default().to_string_lossy();
+        let mut body = json!({
+            "model": name,
+            "files": { filename: format!("sha256:{}", digest) },
+            "parameters": self.parameters()?,
+            "stream": true,
+        });
+        if let Some(template) = self.format.ollama_template() {
+            body["template"] = Value::from(template);
+        }
+        if let Some(system) = &self.system {
+            body["system"] = Value::from(system.as_str());
+        }
+        Ok(body)
+    }
+}
+
+/// Server address, honoring `OLLAMA_HOST` and the config.
+pub fn host() -> String {
+    let host = std::env::var("OLLAMA_HOST")
+        .ok()
+        .filter(|h| !h.trim().is_empty())
+        .or_else(|| config_value("ollama_host", |c| c.ollama_host))
+        .unwrap_or_else(|| OLLAMA_HOST.to_string());
+    normalize_host(&host)
+}
+
+/// Accepts what `ollama serve` does: `host`, `host:port` or a URL. Without
+/// a scheme the port defaults to 11434; `0.0.0.0` (listen everywhere) is
+/// reached on loopback.
+fn normalize_host(host: &str) -> String {
+    let host = host.trim().trim_end_matches('/');
+    let (scheme, rest, default_port) = match host.split_once("://") {
+        Some(("https", rest)) => ("https", rest, "443"),
+        Some((scheme, rest)) => (scheme, rest, "80"),
+        None => ("http", host, "11434"),
+    };
+    let rest = match rest.strip_prefix("0.0.0.0") {
+        Some(port) => format!("127.0.0.1{}", port),
+        None => rest.to_string(),
+    };
+    let has_port = rest.rsplit_once(':').is_some_and(|(_, port)| port.parse::<u16>().is_ok());
+    if has_port {
+        format!("{}://{}", scheme, rest)
+    } else {
+        format!("{}://{}:{}", scheme, rest, default_port)
+    }
+}
+
+impl OllamaClient {
+    pub fn new() -> Self {
+        Self::with_host(&host())
+    }
+
+    pub fn with_host(host: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            host: host.trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// Server version, which doubles as a check that it is running.
+    pub async fn version(&self) -> Result<String> {
+        let response = self
+            .http
+            .get(format!("{}/api/version", self.host))
+            .send()
+            .await
+            .with_context(|| format!("Ollama is not running at {}", self.host))?;
+        let version: Version = check(response).await?.json().await?;
+        Ok(version.version)
+    }
+
+    /// Names of the models the server has, e.g. `aguitachan3/yuuki-best:f32`.
+    pub async fn list_models(&self) -> Result<Vec<String>> {
+        let response = self
+            .http
+            .get(format!("{}/api/tags", self.host))
+            .send()
+            .await
+            .with_context(|| format!("Could not reach Ollama at {}", self.host))?;
+        let tags: Tags = check(response).await?.json().await?;
+        Ok(tags.models.into_iter().map(|m| m.name).collect())
+    }
+
+    pub async fn has_model(&self, model: &str) -> Result<bool> {
+        let wanted = with_tag(model);
+        Ok(self.list_models().await?.iter().any(|m| with_tag(m) == wanted))
+    }
+
+    /// Pulls `model`, calling `progress` for every status line.
+    pub async fn pull(&self, model: &str, mut progress: impl FnMut(&PullProgress)) -> Result<()> {
+        let response = self
+            .http
+            .post(format!("{}/api/pull", self.host))
+            .json(&json!({ "model": model, "stream": true }))
+            .send()
+            .await
+            .with_context(|| format!("Could not reach Ollama at {}", self.host))?;
+        let mut success = false;
+        for_each_line(check(response).await?, |line| {
+            let update: PullProgress = serde_json::from_value(line)?;
+            success |= update.status == "success";
+            progress(&update);
+            Ok(())
+        })
+        .await?;
+        if !success {
+            anyhow::bail!("Ollama stopped pulling {} before it finished", model);
+        }
+        Ok(())
+    }
+
+    pub async fn has_blob(&self, digest: &str) -> Result<bool> {
+        let response = self
+            .http
+            .head(format!("{}/api/blobs/sha256:{}", self.host, digest))
+            .send()
+            .await
+            .with_context(|| format!("Could not reach Ollama at {}", self.host))?;
+        Ok(response.status().is_success())
+    }
+
+    /// Uploads the file at `path` as blob `digest`, calling `progress` with
+    /// the size of every chunk sent.
+    pub async fn push_blob(
+        &self,
+        path: &Path,
+        digest: &str,
+        progress: impl Fn(u64) + Send + Sync + 'static,
+    ) -> Result<()> {
+        let file = tokio::fs::File::open(path)
+            .await
+            .with_context(|| format!("Failed to open {}", path.display()))?;
+        let size = file.metadata().await?.len();
+        let progress = Arc::new(progress);
+        let chunks = futures_util::stream::try_unfold(file, move |mut file| {
+            let progress = Arc::clone(&progress);
+            async move {
+                let mut buf = vec![0u8; 1 << 20];
+                let n = file.read(&mut buf).await?;
+                if n == 0 {
+                    return Ok::<_, std::io::Error>(None);
+                }
+                buf.truncate(n);
+                progress(n as u64);
+                Ok(Some((buf, file)))
+            }
+        });
+
+        let response = self
+            .http
+            .post(format!("{}/api/blobs/sha256:{}", self.host, digest))
+            .header(reqwest::header::CONTENT_LENGTH, size)
+            .body(reqwest::Body::wrap_stream(chunks))
+            .send()
+            .await
+            .with_context(|| format!("Could not upload {} to Ollama", path.display()))?;
+        check(response).await?;
+        Ok(())
+    }
+
+    /// Registers `name` from `modelfile`, whose GGUF was pushed as blob
+    /// `digest`, calling `progress` with each status line.
+    pub async fn create(
+        &self,
+        name: &str,
+        modelfile: &Modelfile,
+        digest: &str,
+        mut progress: impl FnMut(&str),
+    ) -> Result<()> {
+        let response = self
+            .http
+            .post(format!("{}/api/create", self.host))
+            .json(&modelfile.create_request(name, digest)?)
+            .send()
+            .await
+            .with_context(|| format!("Could not reach Ollama at {}", self.host))?;
+        let mut success = false;
+        for_each_line(check(response).await?, |line| {
+            let status = line["status"].as_str().unwrap_or_default();
+            success |= status == "success";
+            progress(status);
+            Ok(())
+        })
+        .await?;
+        if !success {
+            anyhow::bail!("Ollama stopped creating {} before it finished", name);
+        }
+        Ok(())
+    }
+
+    /// Sends the conversation to `/api/chat`, streaming the reply to `out`
+    /// as it arrives.
+    pub async fn chat(
+        &self,
+        model: &str,
+        conversation: &[Message],
+        preset: &Preset,
+        options: &RuntimeOptions,
+        out: &mut dyn Write,
+    ) -> Result<Completion> {
+        let response = self
+            .http
+            .post(format!("{}/api/chat", self.host))
+            .json(&chat_request(model, conversation, preset, options)?)
+            .send()
+            .await
+            .with_context(|| format!("Could not reach Ollama at {}", self.host))?;
+
+        let mut completion = Completion::default();
+        for_each_line(check(response).await?, |line| {
+            if let Some(text) = line["message"]["content"].as_str().filter(|t| !t.is_empty()) {
+                completion.text.push_str(text);
+                out.write_all(text.as_bytes())?;
+                out.flush()?;
+            }
+            // The final line carries the counts
+            completion.prompt_tokens = line["prompt_eval_count"].as_u64().or(completion.prompt_tokens);
+            completion.completion_tokens = line["eval_count"].as_u64().or(completion.completion_tokens);
+            Ok(())
+        })
+        .await?;
+        completion.text = completion.text.trim().to_string();
+        Ok(completion)
+    }
+}
+
+/// `/api/chat` body with the preset and runtime options as model options.
+fn chat_request(model: &str, conversation: &[Message], preset: &Preset, options: &RuntimeOptions) -> Result<Value> {
+    let messages: Vec<Value> = conversation
+        .iter()
+        .map(|m| json!({ "role": m.role.as_str(), "content": m.content }))
+        .collect();
+
+    let options = model_options(preset, options)?;
+    Ok(json!({ "model": model, "messages": messages, "stream": true, "options": options }))
+}
+
+/// A preset and runtime options as Ollama model options.
+fn model_options(preset: &Preset, runtime: &RuntimeOptions) -> Result<Map<String, Value>> {
+    let mut options = preset.sampling_fields();
+    options.insert(
+        "num_ctx".to_string(),
+        Value::from(preset.ctx_size.unwrap_or(DEFAULT_CONTEXT)),
+    );
+    if let Some(n) = preset.n_predict {
+        options.insert("num_predict".to_string(), Value::from(n));
+    }
+    options.extend(runtime.ollama_options()?);
+    Ok(options)
+}
+
+/// Ollama treats a name without a tag as `:latest`.
+fn with_tag(model: &str) -> String {
+    let model = model.to_lowercase();
+    let name = model.rsplit('/').next().unwrap_or(&model);
+    if name.contains(':') {
+        model
+    } else {
+        format!("{}:latest", model)
+    }
+}
+
+/// Turns an error status into the `{"error": ...}` message Ollama sends.
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&text)
+        .ok()
+        .and_then(|v| v["error"].as_str().map(str::to_string))
+        .unwrap_or(text);
+    anyhow::bail!("Ollama returned {}: {}", status, message.trim())
+}
+
+/// Feeds each line of a streamed NDJSON response to `f`, stopping at an
+/// `{"error": ...}` line.
+async fn for_each_line(response: reqwest::Response, mut f: impl FnMut(Value) -> Result<()>) -> Result<()> {
+    let mut stream = response.bytes_stream();
+    let mut pending = Vec::new();
+    let mut handle = |line: &[u8]| -> Result<()> {
+        let line = String::from_utf8_lossy(line);
+        if line.trim().is_empty() {
+            return Ok(());
+        }
+        let value: Value = serde_json::from_str(&line).with_context(|| format!("Unexpected Ollama response: {}", line))?;
+        if let Some(error) = value["error"].as_str() {
+            anyhow::bail!("Ollama: {}", error);
+        }
+        f(value)
+    };
+    while let Some(chunk) = stream.next().await {
+        pending.extend_from_slice(&chunk?);
+        while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = pending.drain(..=end).collect();
+            handle(&line)?;
+        }
+    }
+    handle(&pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Role;
+    use crate::test_support::{serve, Response};
+
+    /// Stand-in Ollama server: answers each request with
+    /// `respond(method, path)` as a 200 NDJSON body, or a 404 for `None`.
+    async fn stand_in(respond: fn(&str, &str) -> Option<&'static str>) -> String {
+        serve(move |request| match respond(&request.method, &request.path) {
+            Some(body) => Response::ok(body).header("Content-Type", "application/x-ndjson"),
+            None => Response::new("404 Not Found", r#"{"error":"model 'nope' not found"}"#),
+        })
+        .await
+    }
+
+    fn api(method: &str, path: &str) -> Option<&'static str> {
+        match (method, path) {
+            ("HEAD", _) => None,
+            ("POST", "/api/blobs/sha256:abc123") => Some(""),
+            ("POST", "/api/create") => Some(concat!(
+                "{\"status\":\"parsing GGUF\"}\n",
+                "{\"status\":\"writing manifest\"}\n",
+                "{\"status\":\"success\"}\n",
+            )),
+            (_, path) => api_path(path),
+        }
+    }
+
+    fn api_path(path: &str) -> Option<&'static str> {
+        match path {
+            "/api/version" => Some(r#"{"version":"0.5.7"}"#),
+            "/api/tags" => Some(
+                r#"{"models":[{"name":"aguitachan3/yuuki-best:f32","size":3400000000},{"name":"llama3.2:latest"}]}"#,
+            ),
+            "/api/pull" => Some(concat!(
+                "{\"status\":\"pulling manifest\"}\n",
+                "{\"status\":\"pulling 8934d96d3f08\",\"digest\":\"sha256:8934d96d3f08\",\"total\":2000,\"completed\":500}\n",
+                "{\"status\":\"pulling 8934d96d3f08\",\"digest\":\"sha256:8934d96d3f08\",\"total\":2000,\"completed\":2000}\n",
+                "{\"status\":\"verifying sha256 digest\"}\n",
+                "{\"status\":\"success\"}\n",
+            )),
+            "/api/chat" => Some(concat!(
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"Hel\"},\"done\":false}\n",
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"lo!\"},\"done\":false}\n",
+                "{\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"prompt_eval_count\":26,\"eval_count\":2}",
+            )),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn normalizes_hosts_like_ollama() {
+        assert_eq!(normalize_host("127.0.0.1:11434"), "http://127.0.0.1:11434");
+        assert_eq!(normalize_host("0.0.0.0"), "http://127.0.0.1:11434");
+        assert_eq!(normalize_host("0.0.0.0:8080"), "http://127.0.0.1:8080");
+        assert_eq!(normalize_host("gpu-box"), "http://gpu-box:11434");
+        assert_eq!(normalize_host("https://ollama.example.com/"), "https://ollama.example.com:443");
+        assert_eq!(normalize_host("http://localhost:11434"), "http://localhost:11434");
+    }
+
+    #[test]
+    fn sends_preset_as_options() {
+        let preset = Preset { temperature: Some(0.3), n_predict: Some(128), ..Preset::default() };
+        let runtime = RuntimeOptions {
+            threads: Some(4),
+            gpu_layers: Some(0),
+            args: vec!["num_batch=256".to_string(), "use_mlock=true".to_string(), "stop=END".to_string()],
+            ..RuntimeOptions::default()
+        };
+        let body = chat_request("yuuki", &[Message::new(Role::User, "hi")], &preset, &runtime).unwrap();
+        assert_eq!(body["messages"][0]["role"], "user");
+        assert_eq!(body["options"]["temperature"], json!(0.3));
+        assert_eq!(body["options"]["num_predict"], json!(128));
+        assert_eq!(body["options"]["num_ctx"], json!(DEFAULT_CONTEXT));
+        assert!(body["options"].get("top_p").is_none());
+        assert_eq!(body["options"]["num_thread"], json!(4));
+        assert_eq!(body["options"]["num_gpu"], json!(0));
+        assert_eq!(body["options"]["num_batch"], json!(256));
+        assert_eq!(body["options"]["use_mlock"], json!(true));
+        assert_eq!(body["options"]["stop"], json!("END"));
+
+        let flag = RuntimeOptions { args: vec!["--mlock".to_string()], ..RuntimeOptions::default() };
+        assert!(chat_request("yuuki", &[], &preset, &flag).unwrap_err().to_string().contains("key=value"));
+    }
+
+    #[tokio::test]
+    async fn talks_to_the_api() {
+        let client = OllamaClient::with_host(&stand_in(api).await);
+        assert_eq!(client.version().await.unwrap(), "0.5.7");
+        assert!(client.has_model("aguitachan3/yuuki-best:F32").await.unwrap());
+        assert!(client.has_model("llama3.2").await.unwrap());
+        assert!(!client.has_model("aguitachan3/yuuki-best:q4_0").await.unwrap());
+
+        let mut updates = Vec::new();
+        client
+            .pull("aguitachan3/yuuki-best:q4_0", |p| updates.push((p.status.clone(), p.completed)))
+            .await
+            .unwrap();
+        assert_eq!(updates.len(), 5);
+        assert_eq!(updates[2], ("pulling 8934d96d3f08".to_string(), Some(2000)));
+
+        let mut out = Vec::new();
+        let reply = client
+            .chat("aguitachan3/yuuki-best:f32", &[Message::new(Role::User, "hi")], &Preset::default(), &RuntimeOptions::default(), &mut out)
+            .await
+            .unwrap();
+        assert_eq!(reply.text, "Hello!");
+        assert_eq!(String::from_utf8(out).unwrap(), "Hello!");
+        assert_eq!((reply.prompt_tokens, reply.completion_tokens), (Some(26), Some(2)));
+    }
+
+    #[tokio::test]
+    async fn reports_errors() {
+        let client = OllamaClient::with_host(&stand_in(|_, _| None).await);
+        let err = client.list_models().await.unwrap_err().to_string();
+        assert!(err.contains("404") && err.contains("model 'nope' not found"), "{}", err);
+
+        let down = OllamaClient::with_host("http://127.0.0.1:9");
+        assert!(down.version().await.unwrap_err().to_string().contains("not running"));
+    }
+
+    fn modelfile() -> Modelfile {
+        Modelfile {
+            gguf: PathBuf::from("/models/Yuuki-best/yuuki-best-q4_0.gguf"),
+            format: ChatFormat::Chatml,
+            system: Some("You are Yuuki.".to_string()),
+            preset: Preset { temperature: Some(0.6), top_p: Some(0.7), ..Preset::default() },
+            options: RuntimeOptions::default(),
+        }
+    }
+
+    #[test]
+    fn renders_modelfile() {
+        let text = modelfile().render().unwrap();
+        assert!(text.contains("FROM /models/Yuuki-best/yuuki-best-q4_0.gguf\n"));
+        assert!(text.contains("TEMPLATE \"\"\"{{- range .Messages }}<|im_start|>{{ .Role }}\n"));
+        assert!(text.contains("SYSTEM \"\"\"You are Yuuki.\"\"\"\n"));
+        assert!(text.contains("PARAMETER temperature 0.6\nPARAMETER top_p 0.7\n"));
+        assert!(text.contains("PARAMETER num_ctx 4096\n"));
+        assert!(text.contains("PARAMETER stop \"<|im_end|>\"\n"));
+
+        let plain = Modelfile { format: ChatFormat::Plain, system: None, ..modelfile() };
+        let text = plain.render().unwrap();
+        assert!(!text.contains("TEMPLATE") && !text.contains("SYSTEM") && !text.contains("stop"));
+
+        let quoted = Modelfile { system: Some("Say \"\"\"hi\"\"\"".to_string()), ..modelfile() };
+        assert!(quoted.render().is_err());
+    }
+
+    #[tokio::test]
+    async fn imports_a_gguf() {
+        let dir = tempfile::tempdir().unwrap();
+        let gguf = dir.path().join("yuuki-best-q4_0.gguf");
+        std::fs::write(&gguf, vec![7u8; 3 << 20]).unwrap();
+        let modelfile = Modelfile { gguf: gguf.clone(), ..modelfile() };
+
+        let body = modelfile.create_request("aguitachan3/yuuki-best:q4_0", "abc123").unwrap();
+        assert_eq!(body["files"]["yuuki-best-q4_0.gguf"], "sha256:abc123");
+        assert_eq!(body["parameters"]["stop"], json!(["<|im_end|>"]));
+        assert_eq!(body["system"], "You are Yuuki.");
+
+        let client = OllamaClient::with_host(&stand_in(api).await);
+        assert!(!client.has_blob("abc123").await.unwrap());
+        let sent = Arc::new(std::sync::atomic::AtomicU64::new(0));
+        let counter = Arc::clone(&sent);
+        client
+            .push_blob(&gguf, "abc123", move |n| {
+                counter.fetch_add(n, std::sync::atomic::Ordering::Relaxed);
+            })
+            .await
+            .unwrap();
+        assert_eq!(sent.load(std::sync::atomic::Ordering::Relaxed), 3 << 20);
+
+        let mut statuses = Vec::new();
+        client
+            .create("aguitachan3/yuuki-best:q4_0", &modelfile, "abc123", |s| statuses.push(s.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(statuses, ["parsing GGUF", "writing manifest", "success"]);
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::get_config_dir;
+use crate::utils::valid_name;
+
+/// Presets shipped with the binary.
+const BUILTIN_PRESETS: &str = include_str!("presets.toml");
+
+/// Preset used when none is given.
+pub const DEFAULT_PRESET: &str = "balanced";
+
+/// Names of the fields `Preset::sampling_fields` can return.
+pub const SAMPLING_FIELDS: &[&str] = &[
+    "temperature",
+    "top_p",
+    "top_k",
+    "min_p",
+    "repeat_penalty",
+    "seed",
+    "mirostat",
+    "mirostat_tau",
+    "mirostat_eta",
+];
+
+/// Sampling and generation parameters behind `--preset`. Unset values keep
+/// the runtime's own default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Preset {
+    /// Taken from the file or table name for user presets
+    #[serde(default, skip_serializing)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub min_p: Option<f32>,
+    pub repeat_penalty: Option<f32>,
+    pub seed: Option<i64>,
+    /// Context window in tokens
+    pub ctx_size: Option<u32>,
+    /// Maximum tokens to generate per reply (-1 = until end of text)
+    pub n_predict: Option<i32>,
+    /// 0 = off, 1 = Mirostat, 2 = Mirostat 2.0
+    pub mirostat: Option<u8>,
+    pub mirostat_tau: Option<f32>,
+    pub mirostat_eta: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PresetFile {
+    #[serde(default)]
+    presets: Vec<Preset>,
+}
+
+impl Preset {
+    /// `(name, value)` for every parameter that is set, for display.
+    pub fn parameters(&self) -> Vec<(&'static str, String)> {
+        let mut params = Vec::new();
+        let mut push = |name: &'static str, value: Option<String>| {
+            if let Some(value) = value {
+                params.push((name, value));
+            }
+        };
+        push("temperature", self.temperature.map(|v| v.to_string()));
+        push("top_p", self.top_p.map(|v| v.to_string()));
+        push("top_k", self.top_k.map(|v| v.to_string()));
+        push("min_p", self.min_p.map(|v| v.to_string()));
+        push("repeat_penalty", self.repeat_penalty.map(|v| v.to_string()));
+        push("seed", self.seed.map(|v| v.to_string()));
+        push("ctx_size", self.ctx_size.map(|v| v.to_string()));
+        push("n_predict", self.n_predict.map(|v| v.to_string()));
+        push("mirostat", self.mirostat.map(|v| v.to_string()));
+        push("mirostat_tau", self.mirostat_tau.map(|v| v.to_string()));
+        push("mirostat_eta", self.mirostat_eta.map(|v| v.to_string()));
+        params
+    }
+
+    /// Sampling parameters as JSON fields under the names llama-server and
+    /// Ollama share. `ctx_size` and `n_predict` are named differently by
+    /// each and left to the caller.
+    pub fn sampling_fields(&self) -> serde_json::Map<String, serde_json::Value> {
+        use serde_json::Value;
+        // Through the decimal string so 0.6f32 is sent as 0.6
+        let float = |v: f32| Value::from(v.to_string().parse::<f64>().unwrap_or(v as f64));
+        // In `SAMPLING_FIELDS` order
+        let values = [
+            self.temperature.map(float),
+            self.top_p.map(float),
+            self.top_k.map(Value::from),
+            self.min_p.map(float),
+            self.repeat_penalty.map(float),
+            self.seed.map(Value::from),
+            self.mirostat.map(Value::from),
+            self.mirostat_tau.map(float),
+            self.mirostat_eta.map(float),
+        ];
+        SAMPLING_FIELDS
+            .iter()
+            .zip(values)
+            .filter_map(|(name, value)| Some((name.to_string(), value?)))
+            .collect()
+    }
+
+    /// This preset with every parameter `overrides` sets replacing its own.
+    pub fn with_overrides(&self, overrides: &Preset) -> Preset {
+        Preset {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            temperature: overrides.temperature.or(self.temperature),
+            top_p: overrides.top_p.or(self.top_p),
+            top_k: overrides.top_k.or(self.top_k),
+            min_p: overrides.min_p.or(self.min_p),
+            repeat_penalty: overrides.repeat_penalty.or(self.repeat_penalty),
+            seed: overrides.seed.or(self.seed),
+            ctx_size: overrides.ctx_size.or(self.ctx_size),
+            n_predict: overrides.n_predict.or(self.n_predict),
+            mirostat: overrides.mirostat.or(self.mirostat),
+            mirostat_tau: overrides.mirostat_tau.or(self.mirostat_tau),
+            mirostat_eta: overrides.mirostat_eta.or(self.mirostat_eta),
+        }
+    }
+
+    /// Rejects values the runtimes would refuse or misinterpret.
+    pub fn validate(&self) -> Result<()> {
+        let check = |ok: bool, what: &str| -> Result<()> {
+            if !ok {
+                anyhow::bail!("Preset '{}': {}", self.name, what);
+            }
+            Ok(())
+        };
+        check(self.temperature.is_none_or(|v| v >= 0.0), "temperature must be >= 0")?;
+        check(self.top_p.is_none_or(|v| (0.0..=1.0).contains(&v)), "top_p must be between 0 and 1")?;
+        check(self.min_p.is_none_or(|v| (0.0..=1.0).contains(&v)), "min_p must be between 0 and 1")?;
+        check(self.repeat_penalty.is_none_or(|v| v > 0.0), "repeat_penalty must be > 0")?;
+        check(self.ctx_size.is_none_or(|v| v > 0), "ctx_size must be > 0")?;
+        check(self.n_predict.is_none_or(|v| v >= -1), "n_predict must be -1 or more")?;
+        check(self.mirostat.is_none_or(|v| v <= 2), "mirostat must be 0, 1 or 2")?;
+        Ok(())
+    }
+}
+
+/// Built-in presets, `[presets.<name>]` tables from the config and
+/// `~/.yuuki/presets/*.toml`, later ones replacing earlier ones by name.
+pub struct PresetStore {
+    dir: PathBuf,
+    config: BTreeMap<String, Preset>,
+}
+
+/// A user preset file that failed to load.
+type BrokenFile = (PathBuf, anyhow::Error);
+
+impl PresetStore {
+    pub fn open() -> Result<Self> {
+        let config = crate::config::load_config()?.presets;
+        Ok(Self::at(get_config_dir()?.join("presets"), config))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>, config: BTreeMap<String, Preset>) -> Self {
+        Self { dir: dir.into(), config }
+    }
+
+    pub fn builtin() -> Vec<Preset> {
+        let file: PresetFile = toml::from_str(BUILTIN_PRESETS).expect("built-in presets are valid");
+        file.presets
+    }
+
+    /// Path of the user file for `name`, which need not exist.
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        if !valid_name(name) {
+            anyhow::bail!("Invalid preset name '{}'. Use letters, digits, '-', '_' and '.'.", name);
+        }
+        Ok(self.dir.join(format!("{}.toml", name)))
+    }
+
+    /// All presets, sorted by name, with where each one comes from. Broken
+    /// user files are skipped with a warning.
+    pub fn list(&self) -> Result<Vec<(Preset, &'static str)>> {
+        let (presets, broken) = self.load_all();
+        for (path, e) in broken {
+            warn_skipped(&path, &e);
+        }
+        Ok(presets)
+    }
+
+    /// Every preset that loads, plus the user files that don't.
+    fn load_all(&self) -> (Vec<(Preset, &'static str)>, Vec<BrokenFile>) {
+        let mut presets: Vec<(Preset, &'static str)> = Vec::new();
+        let mut broken = Vec::new();
+        let mut add = |preset: Preset, source: &'static str| {
+            presets.retain(|(p, _)| !p.name.eq_ignore_ascii_case(&preset.name));
+            presets.push((preset, source));
+        };
+
+        for preset in Self::builtin() {
+            add(preset, "built-in");
+        }
+        for (name, preset) in &self.config {
+            add(Preset { name: name.clone(), ..preset.clone() }, "config");
+        }
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            let mut paths: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+                .collect();
+            paths.sort();
+            for path in paths {
+                match load_file(&path) {
+                    Ok(preset) => add(preset, "user"),
+                    Err(e) => broken.push((path, e)),
+                }
+            }
+        }
+
+        presets.sort_by_key(|(p, _)| p.name.to_lowercase());
+        (presets, broken)
+    }
+
+    /// Looks up `name`, or the default preset when `None`. Fails if the
+    /// user file for `name` is broken, rather than using another layer.
+    pub fn find(&self, name: Option<&str>) -> Result<Preset> {
+        let name = name.unwrap_or(DEFAULT_PRESET);
+        let (presets, broken) = self.load_all();
+        for (path, e) in broken {
+            if path.file_stem().is_some_and(|stem| stem.to_string_lossy().eq_ignore_ascii_case(name)) {
+                return Err(e);
+            }
+            warn_skipped(&path, &e);
+        }
+        if let Some((preset, _)) = presets.iter().find(|(p, _)| p.name.eq_ignore_ascii_case(name)) {
+            preset.validate()?;
+            return Ok(preset.clone());
+        }
+        let names: Vec<_> = presets.iter().map(|(p, _)| p.name.as_str()).collect();
+        anyhow::bail!("Unknown preset '{}'. Available: {}", name, names.join(", "))
+    }
+
+    /// Writes `preset` to its user file.
+    pub fn save(&self, preset: &Preset) -> Result<PathBuf> {
+        let path = self.path(&preset.name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, toml::to_string_pretty(preset)?)?;
+        Ok(path)
+    }
+}
+
+fn warn_skipped(path: &Path, e: &anyhow::Error) {
+    eprintln!("{} Skipping {}: {:#}", "⚠".bright_yellow(), path.display(), e);
+}
+
+/// Loads a user preset file, naming it after the file.
+pub fn load_file(path: &Path) -> Result<Preset> {
+    let content = fs::read_to_string(path)?;
+    let mut preset: Preset =
+        toml::from_str(&content).with_context(|| format!("Invalid preset file {}", path.display()))?;
+    preset.name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(preset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layers_config_and_files_over_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = BTreeMap::from([
+            ("precise".to_string(), Preset { temperature: Some(0.1), ..Preset::default() }),
+            ("long".to_string(), Preset { ctx_size: Some(16384), n_predict: Some(-1), ..Preset::default() }),
+        ]);
+        let store = PresetStore::at(dir.path(), config);
+        std::fs::write(dir.path().join("long.toml"), "ctx_size = 32768\nmirostat = 2\n").unwrap();
+
+        assert_eq!(store.find(None).unwrap().temperature, Some(0.6));
+        assert_eq!(store.find(Some("PRECISE")).unwrap().temperature, Some(0.1));
+
+        let long = store.find(Some("long")).unwrap();
+        assert_eq!(long.ctx_size, Some(32768));
+        assert_eq!(long.n_predict, None);
+        assert_eq!(long.mirostat, Some(2));
+
+        let sources: Vec<_> = store.list().unwrap().into_iter().map(|(p, s)| (p.name, s)).collect();
+        assert!(sources.contains(&("long".to_string(), "user")));
+        assert!(sources.contains(&("precise".to_string(), "config")));
+    }
+
+    #[test]
+    fn unknown_and_invalid_presets_are_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PresetStore::at(dir.path(), BTreeMap::new());
+        let err = store.find(Some("wild")).unwrap_err().to_string();
+        assert!(err.contains("Unknown preset 'wild'"), "{}", err);
+        assert!(err.contains("balanced, creative, precise"), "{}", err);
+
+        std::fs::write(dir.path().join("hot.toml"), "top_p = 1.5\n").unwrap();
+        assert!(store.find(Some("hot")).is_err());
+        std::fs::write(dir.path().join("typo.toml"), "temprature = 1.0\n").unwrap();
+        let err = store.find(Some("TYPO")).unwrap_err().to_string();
+        assert!(err.contains("Invalid preset file"), "{}", err);
+    }
+
+    #[test]
+    fn skips_broken_files_that_are_not_requested() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = PresetStore::at(dir.path(), BTreeMap::new());
+        std::fs::write(dir.path().join("typo.toml"), "temprature = 1.0\n").unwrap();
+        std::fs::write(dir.path().join("cold.toml"), "temperature = 0.2\n").unwrap();
+
+        let names: Vec<_> = store.list().unwrap().into_iter().map(|(p, _)| p.name).collect();
+        assert!(names.contains(&"cold".to_string()));
+        assert!(!names.contains(&"typo".to_string()));
+        assert_eq!(store.find(Some("cold")).unwrap().temperature, Some(0.2));
+        assert_eq!(store.find(None).unwrap().name, "balanced");
+    }
+}
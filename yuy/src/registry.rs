@@ -0,0 +1,345 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::config::{get_cache_dir, get_config_dir, OLLAMA_ORG};
+use crate::settings::{Setting, Source};
+
+/// Registry shipped with the binary.
+const BUILTIN_REGISTRY: &str = include_str!("registry.toml");
+
+pub const DEFAULT_REGISTRY_URL: &str =
+    "https://raw.githubusercontent.com/YuuKi-OS/yuy/main/yuy/src/registry.toml";
+
+/// How GGUF files are named inside a repo.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileNaming {
+    /// `yuuki-nxg-3b-q4_k_m.gguf`
+    #[default]
+    LowercaseDash,
+    /// `Yuuki-NxG-vl.Q4_K_M.gguf` (mradermacher's quants)
+    DotUppercase,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct QuantInfo {
+    pub name: String,
+    /// File size in bytes
+    pub size: u64,
+}
+
+/// Everything Yuy needs to know to fetch and run a model.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelEntry {
+    /// Display name, also the directory under `~/.yuuki/models`
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    /// Parameter count, e.g. `7B`
+    pub parameters: Option<String>,
+    /// `text`, `vision+text`, ...
+    pub modality: Option<String>,
+    pub license: Option<String>,
+    /// Repo with the original weights
+    pub source_repo: String,
+    /// Repo with GGUF quantizations, when they live somewhere else
+    pub gguf_repo: Option<String>,
+    #[serde(default)]
+    pub naming: FileNaming,
+    /// Known quantizations; the repo listing is authoritative when reachable
+    #[serde(default)]
+    pub quants: Vec<QuantInfo>,
+}
+
+/// A model given on the command line, with the quant from a `:quant` suffix.
+#[derive(Debug, Clone)]
+pub struct ModelRef {
+    pub entry: ModelEntry,
+    pub quant: Option<String>,
+}
+
+impl ModelRef {
+    /// Combines the `:quant` suffix with a `--quant` flag; they must agree.
+    pub fn quant_or(&self, flag: Option<String>) -> Result<Option<String>> {
+        match (&self.quant, flag) {
+            (Some(a), Some(b)) if !a.eq_ignore_ascii_case(&b) => {
+                anyhow::bail!("Conflicting quantizations: '{}' in the model name and '{}' from --quant", a, b)
+            }
+            (Some(a), _) => Ok(Some(a.clone())),
+            (None, flag) => Ok(flag),
+        }
+    }
+
+    /// `quant_or`, remembering which of the two named the quantization.
+    pub fn quant_setting(&self, flag: Option<String>) -> Result<Option<Setting<String>>> {
+        let source = if self.quant.is_some() { Source::Flag("model:quant") } else { Source::Flag("--quant") };
+        Ok(self.quant_or(flag)?.map(|quant| Setting::new(quant, source)))
+    }
+}
+
+impl ModelEntry {
+    /// Entry for a Hugging Face repo that is not in the registry.
+    pub fn from_repo(repo: &str) -> Self {
+        Self {
+            name: repo.to_string(),
+            description: String::new(),
+            parameters: None,
+            modality: None,
+            license: None,
+            source_repo: repo.to_string(),
+            gguf_repo: None,
+            naming: FileNaming::default(),
+            quants: Vec::new(),
+        }
+    }
+
+    /// Whether this is an `org/repo` reference rather than a registry model.
+    /// Its local files live in the namespaced `models/<org>/<repo>` directory.
+    pub fn is_repo_ref(&self) -> bool {
+        self.name.contains('/')
+    }
+
+    /// Repo that GGUF files are downloaded from.
+    pub fn download_repo(&self) -> &str {
+        self.gguf_repo.as_deref().unwrap_or(&self.source_repo)
+    }
+
+    /// Expected file name for `quant` according to the naming scheme.
+    pub fn filename(&self, quant: &str) -> String {
+        let base = self.name.rsplit('/').next().unwrap_or(&self.name);
+        match self.naming {
+            FileNaming::LowercaseDash => {
+                format!("{}-{}.gguf", base.to_lowercase(), quant.to_lowercase())
+            }
+            FileNaming::DotUppercase => format!("{}.{}.gguf", base, quant.to_uppercase()),
+        }
+    }
+
+    pub fn quant(&self, name: &str) -> Option<&QuantInfo> {
+        self.quants.iter().find(|q| q.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Ollama model reference, e.g. `aguitachan3/yuuki-nxg-3b:q4_k_m`.
+    /// Repo references use Ollama's `hf.co/<org>/<repo>:<quant>` syntax.
+    pub fn ollama_model(&self, quant: &str) -> String {
+        if self.is_repo_ref() {
+            format!("hf.co/{}:{}", self.download_repo(), quant.to_uppercase())
+        } else {
+            format!("{}/{}:{}", OLLAMA_ORG, self.name.to_lowercase(), quant)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    models: Vec<ModelEntry>,
+}
+
+/// The models Yuy knows about.
+///
+/// Layers, later ones replacing entries with the same name or adding new
+/// ones: the built-in registry, the copy fetched by `yuy registry update`,
+/// and the user's `~/.yuuki/registry.toml`.
+#[derive(Debug, Clone)]
+pub struct Registry {
+    models: Vec<ModelEntry>,
+}
+
+impl Registry {
+    pub fn builtin() -> Self {
+        let file = parse(BUILTIN_REGISTRY).expect("built-in registry is valid");
+        Self { models: file.models }
+    }
+
+    pub fn load() -> Result<Self> {
+        let mut registry = Self::builtin();
+        for path in [get_cached_registry_path()?, get_user_registry_path()?] {
+            registry.merge_file(&path)?;
+        }
+        Ok(registry)
+    }
+
+    fn merge_file(&mut self, path: &Path) -> Result<()> {
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = fs::read_to_string(path)?;
+        let file = parse(&content).with_context(|| format!("Invalid registry file {}", path.display()))?;
+        self.merge(file.models);
+        Ok(())
+    }
+
+    fn merge(&mut self, models: Vec<ModelEntry>) {
+        for model in models {
+            match self.models.iter_mut().find(|m| m.name.eq_ignore_ascii_case(&model.name)) {
+                Some(existing) => *existing = model,
+                None => self.models.push(model),
+            }
+        }
+    }
+
+    pub fn models(&self) -> &[ModelEntry] {
+        &self.models
+    }
+
+    /// Looks up a model by name (case-insensitive).
+    pub fn find(&self, name: &str) -> Option<&ModelEntry> {
+        self.models.iter().find(|m| m.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Resolves `name[:quant]` or `org/repo[:quant]`.
+    ///
+    /// A repo that belongs to a registry model resolves to that model, any
+    /// other repo to an ad-hoc entry.
+    pub fn resolve(&self, spec: &str) -> Result<ModelRef> {
+        let (name, quant) = match spec.rsplit_once(':') {
+            Some((name, quant)) if !quant.is_empty() => (name, Some(quant.to_lowercase())),
+            _ => (spec, None),
+        };
+
+        if let Some(entry) = self.find(name) {
+            return Ok(ModelRef { entry: entry.clone(), quant });
+        }
+
+        if !name.contains('/') {
+            let names: Vec<_> = self.models.iter().map(|m| m.name.as_str()).collect();
+            anyhow::bail!(
+                "Model '{}' not found. Available: {}. Hugging Face repos can be given as org/repo[:quant].",
+                name,
+                names.join(", ")
+            );
+        }
+
+        validate_repo_id(name)?;
+        let entry = self
+            .models
+            .iter()
+            .find(|m| {
+                m.source_repo.eq_ignore_ascii_case(name)
+                    || m.gguf_repo.as_deref().is_some_and(|r| r.eq_ignore_ascii_case(name))
+            })
+            .cloned()
+            .unwrap_or_else(|| ModelEntry::from_repo(name));
+
+        Ok(ModelRef { entry, quant })
+    }
+}
+
+/// Checks that `repo` is `org/name` made of characters Hugging Face allows,
+/// which also keeps it from escaping the models directory.
+fn validate_repo_id(repo: &str) -> Result<()> {
+    let valid_part = |part: &str| {
+        !part.is_empty()
+            && part != "."
+            && part != ".."
+            && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+    };
+
+    match repo.split_once('/') {
+        Some((org, name)) if valid_part(org) && valid_part(name) => Ok(()),
+        _ => anyhow::bail!("Invalid Hugging Face repo '{}'. Expected org/repo[:quant].", repo),
+    }
+}
+
+fn parse(content: &str) -> Result<RegistryFile> {
+    Ok(toml::from_str(content)?)
+}
+
+/// Validates a registry document, returning how many models it defines.
+pub fn validate(content: &str) -> Result<usize> {
+    Ok(parse(content)?.models.len())
+}
+
+/// User overrides and additions.
+pub fn get_user_registry_path() -> Result<PathBuf> {
+    Ok(get_config_dir()?.join("registry.toml"))
+}
+
+/// Last registry fetched by `yuy registry update`.
+pub fn get_cached_registry_path() -> Result<PathBuf> {
+    Ok(get_cache_dir()?.join("registry-cache.toml"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gguf_mirror_takes_precedence_over_source_repo() {
+        let registry = Registry::builtin();
+
+        let vl = registry.find("yuuki-nxg-vl").unwrap();
+        assert_eq!(vl.name, "Yuuki-NxG-vl");
+        assert_eq!(vl.download_repo(), "mradermacher/Yuuki-NxG-vl-GGUF");
+        assert_eq!(vl.filename("q4_k_m"), "Yuuki-NxG-vl.Q4_K_M.gguf");
+
+        let nano = registry.find("Yuuki-NxG-Nano").unwrap();
+        assert_eq!(nano.download_repo(), "OpceanAI/Yuuki-NxG-Nano");
+        assert_eq!(nano.filename("Q8_0"), "yuuki-nxg-nano-q8_0.gguf");
+    }
+
+    #[test]
+    fn builtin_models_list_quant_sizes() {
+        for entry in Registry::builtin().models() {
+            assert!(entry.quant("q4_k_m").is_some_and(|q| q.size > 0), "{}", entry.name);
+        }
+    }
+
+    #[test]
+    fn user_entries_override_and_extend_builtin() {
+        let mut registry = Registry::builtin();
+        let user = parse(
+            r#"
+            [[models]]
+            name = "yuuki-nxg-nano"
+            source_repo = "OpceanAI/Yuuki-NxG-Nano"
+            gguf_repo = "someone/Yuuki-NxG-Nano-GGUF"
+            naming = "dot-uppercase"
+
+            [[models]]
+            name = "Yuuki-NxG-Next"
+            description = "Preview release"
+            source_repo = "OpceanAI/Yuuki-NxG-Next"
+            quants = [{ name = "q4_k_m", size = 1000 }]
+            "#,
+        )
+        .unwrap();
+        registry.merge(user.models);
+
+        assert_eq!(registry.models().len(), 4);
+        let nano = registry.find("Yuuki-NxG-Nano").unwrap();
+        assert_eq!(nano.download_repo(), "someone/Yuuki-NxG-Nano-GGUF");
+        let next = registry.find("Yuuki-NxG-Next").unwrap();
+        assert_eq!(next.quant("Q4_K_M").unwrap().size, 1000);
+    }
+
+    #[test]
+    fn resolves_repo_references() {
+        let registry = Registry::builtin();
+
+        let r = registry.resolve("someone/Yuuki-NxG-ft-GGUF:Q4_K_M").unwrap();
+        assert!(r.entry.is_repo_ref());
+        assert_eq!(r.entry.download_repo(), "someone/Yuuki-NxG-ft-GGUF");
+        assert_eq!(r.quant.as_deref(), Some("q4_k_m"));
+        assert_eq!(r.entry.ollama_model("q4_k_m"), "hf.co/someone/Yuuki-NxG-ft-GGUF:Q4_K_M");
+
+        // Repos that belong to a registry model resolve to it
+        let r = registry.resolve("mradermacher/Yuuki-NxG-vl-GGUF").unwrap();
+        assert_eq!(r.entry.name, "Yuuki-NxG-vl");
+        assert_eq!(r.quant, None);
+
+        assert!(registry.resolve("Yuuki-Unknown").is_err());
+        assert!(registry.resolve("../etc").is_err());
+        assert!(registry.resolve("a/b/c").is_err());
+    }
+
+    #[test]
+    fn quant_suffix_and_flag_must_agree() {
+        let r = Registry::builtin().resolve("Yuuki-NxG-vl:q8_0").unwrap();
+        assert_eq!(r.quant_or(None).unwrap().as_deref(), Some("q8_0"));
+        assert_eq!(r.quant_or(Some("Q8_0".into())).unwrap().as_deref(), Some("q8_0"));
+        assert!(r.quant_or(Some("q4_k_m".into())).is_err());
+    }
+}
@@ -0,0 +1,425 @@
+use anyhow::{Context, Result};
+use futures_util::StreamExt;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+use tokio::task::AbortHandle;
+use crate::backend::{ChatFormat, Completion, Interrupted, ModelSource, RuntimeExit, RuntimeOptions};
+use crate::preset::Preset;
+use crate::session::Message;
+use crate::utils::command_exists;
+
+/// Lines of server output kept for error messages.
+const LOG_TAIL: usize = 20;
+
+/// How long `stop` waits for the server to exit before killing it.
+const SHUTDOWN_GRACE: Duration = Duration::from_secs(5);
+
+/// How long `start` waits for the model to load when the config doesn't
+/// set `server_timeout`.
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// A `llama-server` process serving one model on a local port. The model
+/// stays loaded between turns; the server is stopped when this is dropped.
+/// Ctrl-C fails its requests with `Interrupted`, so the caller can save
+/// its work and drop it.
+#[derive(Debug)]
+pub struct LlamaServer {
+    binary: String,
+    source: ModelSource,
+    url: String,
+    child: Arc<Mutex<Option<Child>>>,
+    log: Arc<Mutex<VecDeque<String>>>,
+    log_reader: Mutex<Option<JoinHandle<()>>>,
+    /// Turns true on Ctrl-C
+    interrupted: watch::Receiver<bool>,
+    ctrl_c: AbortHandle,
+}
+
+impl LlamaServer {
+    /// The `llama-server` binary, if it is on `PATH`.
+    pub fn find() -> Option<&'static str> {
+        command_exists("llama-server").then_some("llama-server")
+    }
+
+    /// Starts `binary` on a free local port and waits, up to `timeout`,
+    /// until the model is loaded. `slots` requests can be served at once,
+    /// each with a context of `ctx_size` tokens.
+    pub async fn start(
+        binary: &str,
+        source: &ModelSource,
+        format: ChatFormat,
+        ctx_size: u32,
+        slots: usize,
+        options: &RuntimeOptions,
+        timeout: Duration,
+    ) -> Result<Self> {
+        let port = free_port()?;
+        let slots = slots.max(1);
+
+        let mut cmd = Command::new(binary);
+        match source {
+            ModelSource::File(path) => cmd.arg("-m").arg(path),
+            ModelSource::HuggingFace(hf) => cmd.arg("-hf").arg(hf),
+        };
+        cmd.args(["--host", "127.0.0.1", "--port", &port.to_string()])
+            .args(["-c", &(ctx_size as usize * slots).to_string()])
+            .args(["--parallel", &slots.to_string()]);
+        if let Some(template) = format.server_template() {
+            cmd.args(["--chat-template", template]);
+        }
+        cmd.args(options.llama_args());
+        // Keep Ctrl-C from reaching the server directly so it is stopped in order
+        #[cfg(unix)]
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+
+        let mut child = cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", binary))?;
+
+        let log = Arc::new(Mutex::new(VecDeque::new()));
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let tail = Arc::clone(&log);
+        let log_reader = std::thread::spawn(move || {
+            for line in BufReader::new(stderr).lines().map_while(|l| l.ok()) {
+                let mut tail = tail.lock().unwrap();
+                if tail.len() == LOG_TAIL {
+                    tail.pop_front();
+                }
+                tail.push_back(line);
+            }
+        });
+
+        let (ctrl_c, interrupted) = watch_ctrl_c();
+        let server = Self {
+            binary: binary.to_string(),
+            source: source.clone(),
+            url: format!("http://127.0.0.1:{}", port),
+            child: Arc::new(Mutex::new(Some(child))),
+            log,
+            log_reader: Mutex::new(Some(log_reader)),
+            interrupted,
+            ctrl_c,
+        };
+        server.wait_until_ready(timeout).await?;
+        Ok(server)
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn source(&self) -> &ModelSource {
+        &self.source
+    }
+
+    /// Resolves once Ctrl-C is pressed while the server runs.
+    pub async fn interrupted(&self) {
+        if *self.interrupted.borrow() {
+            return;
+        }
+        let mut interrupted = self.interrupted.clone();
+        if interrupted.wait_for(|&yes| yes).await.is_err() {
+            // The handler couldn't listen for Ctrl-C
+            std::future::pending::<()>().await;
+        }
+    }
+
+    /// Sends the conversation to `/v1/chat/completions`, streaming the reply
+    /// to `out` as it arrives.
+    pub async fn chat(&self, conversation: &[Message], preset: &Preset, out: &mut dyn Write) -> Result<Completion> {
+        let body = request_body(conversation, preset);
+        let request = async {
+            let response = reqwest::Client::new()
+                .post(format!("{}/v1/chat/completions", self.url))
+                .json(&body)
+                .send()
+                .await?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let text = response.text().await.unwrap_or_default();
+                let message = serde_json::from_str::<Value>(&text)
+                    .ok()
+                    .and_then(|v| v["error"]["message"].as_str().map(str::to_string))
+                    .unwrap_or(text);
+                anyhow::bail!("{} returned {}: {}", self.binary, status, message.trim());
+            }
+
+            let mut completion = Completion::default();
+            let mut stream = response.bytes_stream();
+            let mut pending = Vec::new();
+            while let Some(chunk) = stream.next().await {
+                pending.extend_from_slice(&chunk?);
+                while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                    let line: Vec<u8> = pending.drain(..=end).collect();
+                    if let Some(text) = read_event(&String::from_utf8_lossy(&line), &mut completion)? {
+                        out.write_all(text.as_bytes())?;
+                        out.flush()?;
+                    }
+                }
+            }
+            Ok(completion)
+        };
+        let result = tokio::select! {
+            result = request => result,
+            _ = self.interrupted() => return Err(Interrupted.into()),
+        };
+        // A dropped connection usually means the server died; say how
+        result.map_err(|e| self.exit_error().map_or(e, Into::into))
+    }
+
+    /// Polls `/health` until the model is loaded, failing if the server
+    /// exits first or `timeout` passes. The server is stopped on drop.
+    async fn wait_until_ready(&self, timeout: Duration) -> Result<()> {
+        let health = format!("{}/health", self.url);
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(2)).build()?;
+        let deadline = Instant::now() + timeout;
+        loop {
+            if *self.interrupted.borrow() {
+                return Err(Interrupted.into());
+            }
+            if let Some(exit) = self.exit_error() {
+                return Err(exit.into());
+            }
+            // 503 while the model loads, connection refused before that
+            let ready = client.get(&health).send().await.is_ok_and(|r| r.status().is_success());
+            if ready {
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                let log = self.log.lock().unwrap();
+                let output: String = log.iter().map(|l| format!("\n  {}", l)).collect();
+                anyhow::bail!(
+                    "{} did not load the model within {}s. Raise server_timeout in the config for large models. Last output:{}",
+                    self.binary,
+                    timeout.as_secs(),
+                    if output.is_empty() { " (none)".to_string() } else { output }
+                );
+            }
+            tokio::time::sleep(Duration::from_millis(200)).await;
+        }
+    }
+
+    /// The server's exit status as an error, if it is no longer running.
+    fn exit_error(&self) -> Option<RuntimeExit> {
+        let mut child = self.child.lock().unwrap();
+        let status = child.as_mut()?.try_wait().ok()??;
+        *child = None;
+        // The reader ends with the process, after its last lines
+        if let Some(reader) = self.log_reader.lock().unwrap().take() {
+            let _ = reader.join();
+        }
+        let log = self.log.lock().unwrap();
+        let detail = log.iter().rev().find(|l| !l.trim().is_empty()).map_or("", |l| l.trim());
+        Some(RuntimeExit {
+            runtime: self.binary.clone(),
+            code: status.code(),
+            detail: detail.to_string(),
+        })
+    }
+}
+
+impl Drop for LlamaServer {
+    fn drop(&mut self) {
+        self.ctrl_c.abort();
+        if let Some(child) = self.child.lock().unwrap().take() {
+            stop(child);
+        }
+    }
+}
+
+/// Asks the server to shut down, killing it if it hasn't after a grace period.
+fn stop(mut child: Child) {
+    #[cfg(unix)]
+    {
+        // SAFETY: kill(2) only sends a signal; the pid is our own child,
+        // which hasn't been reaped yet
+        let asked = unsafe { libc::kill(child.id() as libc::pid_t, libc::SIGTERM) } == 0;
+        let deadline = Instant::now() + SHUTDOWN_GRACE;
+        while asked && Instant::now() < deadline {
+            if let Ok(Some(_)) = child.try_wait() {
+                return;
+            }
+            std::thread::sleep(Duration::from_millis(50));
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+/// Listens for Ctrl-C until aborted, flipping the returned flag when it
+/// comes. The server itself is left running until it is dropped.
+fn watch_ctrl_c() -> (AbortHandle, watch::Receiver<bool>) {
+    let (tx, rx) = watch::channel(false);
+    let task = tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            let _ = tx.send(true);
+        }
+    });
+    (task.abort_handle(), rx)
+}
+
+/// A port nothing is listening on right now.
+fn free_port() -> Result<u16> {
+    let listener = TcpListener::bind("127.0.0.1:0").context("No free local port for llama-server")?;
+    Ok(listener.local_addr()?.port())
+}
+
+/// OpenAI-style request with llama.cpp's extra sampling fields.
+fn request_body(conversation: &[Message], preset: &Preset) -> Value {
+    let messages: Vec<Value> = conversation
+        .iter()
+        .map(|m| json!({ "role": m.role.as_str(), "content": m.content }))
+        .collect();
+    let mut body = json!({
+        "messages": messages,
+        "stream": true,
+        "stream_options": { "include_usage": true },
+    });
+
+    let fields = body.as_object_mut().expect("body is an object");
+    fields.extend(preset.sampling_fields());
+    if let Some(n) = preset.n_predict.filter(|&n| n >= 0) {
+        fields.insert("max_tokens".to_string(), Value::from(n));
+    }
+    body
+}
+
+/// Applies one server-sent event line to `completion`, returning the text
+/// it adds.
+fn read_event(line: &str, completion: &mut Completion) -> Result<Option<String>> {
+    let Some(data) = line.trim().strip_prefix("data:").map(str::trim) else {
+        return Ok(None);
+    };
+    if data == "[DONE]" {
+        return Ok(None);
+    }
+    let event: Value = serde_json::from_str(data).with_context(|| format!("Unexpected server event: {}", data))?;
+    if let Some(message) = event["error"]["message"].as_str() {
+        anyhow::bail!("llama-server: {}", message);
+    }
+
+    // Usage arrives with the last chunk; older servers only send timings
+    let count = |v: &Value| v.as_u64();
+    if let Some(n) = count(&event["usage"]["prompt_tokens"]).or_else(|| count(&event["timings"]["prompt_n"])) {
+        completion.prompt_tokens = Some(n);
+    }
+    if let Some(n) = count(&event["usage"]["completion_tokens"]).or_else(|| count(&event["timings"]["predicted_n"])) {
+        completion.completion_tokens = Some(n);
+    }
+
+    let text = event["choices"][0]["delta"]["content"].as_str().unwrap_or_default();
+    if text.is_empty() {
+        return Ok(None);
+    }
+    completion.text.push_str(text);
+    Ok(Some(text.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Role;
+    #[cfg(unix)]
+    use crate::test_support::fake_binary;
+    use std::path::PathBuf;
+
+    #[test]
+    fn reads_stream_events() {
+        let mut completion = Completion::default();
+        let events = [
+            r#"data: {"choices":[{"delta":{"role":"assistant"}}]}"#,
+            r#"data: {"choices":[{"delta":{"content":"Hel"}}]}"#,
+            "",
+            r#"data: {"choices":[{"delta":{"content":"lo"}}],"timings":{"prompt_n":9,"predicted_n":2}}"#,
+            r#"data: {"choices":[],"usage":{"prompt_tokens":12,"completion_tokens":2}}"#,
+            "data: [DONE]",
+        ];
+        let shown: Vec<_> = events
+            .iter()
+            .filter_map(|e| read_event(e, &mut completion).unwrap())
+            .collect();
+        assert_eq!(shown, ["Hel", "lo"]);
+        assert_eq!(completion.text, "Hello");
+        assert_eq!((completion.prompt_tokens, completion.completion_tokens), (Some(12), Some(2)));
+
+        let err = read_event(r#"data: {"error":{"message":"context full"}}"#, &mut completion).unwrap_err();
+        assert!(err.to_string().contains("context full"));
+    }
+
+    #[test]
+    fn sends_preset_as_request_fields() {
+        let preset = Preset { temperature: Some(0.6), top_k: Some(40), n_predict: Some(-1), ..Preset::default() };
+        let body = request_body(&[Message::new(Role::User, "hi")], &preset);
+        assert_eq!(body["messages"][0], json!({ "role": "user", "content": "hi" }));
+        assert_eq!(body["temperature"], json!(0.6));
+        assert_eq!(body["top_k"], json!(40));
+        assert!(body.get("max_tokens").is_none());
+        assert!(body.get("top_p").is_none());
+    }
+
+    async fn start(binary: &str, model: &str, timeout: Duration) -> Result<LlamaServer> {
+        let source = ModelSource::File(PathBuf::from(model));
+        LlamaServer::start(binary, &source, ChatFormat::Chatml, 4096, 2, &RuntimeOptions::default(), timeout).await
+    }
+
+    #[tokio::test]
+    async fn drives_fake_server() {
+        // The llama-server stand-in is a Python script
+        if !command_exists("python3") {
+            eprintln!("skipping drives_fake_server: python3 not found");
+            return;
+        }
+        let binary = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/fake-llama-server");
+        let server = start(binary, "model.gguf", DEFAULT_READY_TIMEOUT).await.unwrap();
+
+        let preset = Preset { temperature: Some(0.2), ..Preset::default() };
+        let mut out = Vec::new();
+        let reply = server
+            .chat(&[Message::new(Role::User, "hi there")], &preset, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(reply.text, "echo: hi there (temperature 0.2)");
+        assert_eq!(String::from_utf8(out).unwrap(), reply.text);
+        assert_eq!((reply.prompt_tokens, reply.completion_tokens), (Some(1), Some(5)));
+
+        let err = server
+            .chat(&[Message::new(Role::User, "bad")], &preset, &mut Vec::new())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("400"), "{}", err);
+
+        // Dropping stops the server
+        let url = server.url().trim_start_matches("http://").to_string();
+        drop(server);
+        assert!(std::net::TcpStream::connect(url).is_err());
+
+        let err = start(binary, "broken.gguf", DEFAULT_READY_TIMEOUT).await.unwrap_err();
+        let exit = err.downcast_ref::<RuntimeExit>().unwrap();
+        assert_eq!(exit.code, Some(1));
+        assert_eq!(exit.detail, "error: failed to load model 'broken.gguf'");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn gives_up_on_a_server_that_never_loads() {
+        let dir = tempfile::tempdir().unwrap();
+        let script = fake_binary(dir.path(), "llama-server", "echo 'loading model' >&2\nexec sleep 30\n");
+
+        let started = Instant::now();
+        let err = start(&script.to_string_lossy(), "model.gguf", Duration::from_secs(1)).await.unwrap_err().to_string();
+        assert!(err.contains("within 1s"), "{}", err);
+        assert!(err.ends_with("\n  loading model"), "{}", err);
+        // Stopped with SIGTERM rather than waiting out the grace period
+        assert!(started.elapsed() < SHUTDOWN_GRACE, "{:?}", started.elapsed());
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use crate::config::get_data_dir;
+use crate::utils::valid_name;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    pub role: Role,
+    pub content: String,
+}
+
+impl Message {
+    pub fn new(role: Role, content: impl Into<String>) -> Self {
+        Self { role, content: content.into() }
+    }
+}
+
+/// A conversation with a model, saved as `~/.yuuki/sessions/<name>.json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    pub name: String,
+    pub model: String,
+    pub quant: Option<String>,
+    pub runtime: Option<String>,
+    pub preset: Option<String>,
+    /// Template the system prompt came from; also sets the chat format
+    #[serde(default)]
+    pub template: Option<String>,
+    pub system_prompt: Option<String>,
+    /// Unix timestamps in seconds
+    pub created_at: u64,
+    pub updated_at: u64,
+    #[serde(default)]
+    pub messages: Vec<Message>,
+}
+
+impl Session {
+    pub fn new(name: &str, model: &str) -> Self {
+        let now = now();
+        Self {
+            name: name.to_string(),
+            model: model.to_string(),
+            quant: None,
+            runtime: None,
+            preset: None,
+            template: None,
+            system_prompt: None,
+            created_at: now,
+            updated_at: now,
+            messages: Vec::new(),
+        }
+    }
+
+    /// The system prompt followed by the message history.
+    pub fn conversation(&self) -> Vec<Message> {
+        let system = self
+            .system_prompt
+            .as_ref()
+            .map(|prompt| Message::new(Role::System, prompt.clone()));
+        system.into_iter().chain(self.messages.iter().cloned()).collect()
+    }
+
+    /// Renders the conversation as Markdown for `yuy sessions export`.
+    pub fn to_markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.name);
+        out.push_str(&format!("- Model: {}\n", self.model));
+        if let Some(quant) = &self.quant {
+            out.push_str(&format!("- Quantization: {}\n", quant));
+        }
+        if let Some(preset) = &self.preset {
+            out.push_str(&format!("- Preset: {}\n", preset));
+        }
+        if let Some(template) = &self.template {
+            out.push_str(&format!("- Template: {}\n", template));
+        }
+        out.push_str(&format!("- Created: {}\n", crate::utils::format_timestamp(self.created_at)));
+        for message in self.conversation() {
+            let heading = match message.role {
+                Role::System => "System",
+                Role::User => "User",
+                Role::Assistant => "Assistant",
+            };
+            out.push_str(&format!("\n## {}\n\n{}\n", heading, message.content.trim_end()));
+        }
+        out
+    }
+}
+
+/// Default name for a new session, e.g. `20261018-140312`.
+pub fn generate_name() -> String {
+    let (y, mo, d, h, mi, s) = crate::utils::utc_datetime(now());
+    format!("{:04}{:02}{:02}-{:02}{:02}{:02}", y, mo, d, h, mi, s)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// Session names become file names, so keep them to a safe character set.
+fn validate_name(name: &str) -> Result<()> {
+    if !valid_name(name) {
+        anyhow::bail!(
+            "Invalid session name '{}'. Use letters, digits, '-', '_' and '.'.",
+            name
+        );
+    }
+    Ok(())
+}
+
+/// Directory of saved sessions.
+pub struct SessionStore {
+    dir: PathBuf,
+}
+
+impl SessionStore {
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(get_data_dir()?.join("sessions")))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        validate_name(name)?;
+        Ok(self.dir.join(format!("{}.json", name)))
+    }
+
+    pub fn exists(&self, name: &str) -> Result<bool> {
+        Ok(self.path(name)?.exists())
+    }
+
+    pub fn load(&self, name: &str) -> Result<Session> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            anyhow::bail!("Session '{}' not found. See: yuy sessions list", name);
+        }
+        let content = fs::read_to_string(&path)?;
+        serde_json::from_str(&content).with_context(|| format!("Invalid session file {}", path.display()))
+    }
+
+    /// Writes the session, bumping `updated_at`.
+    pub fn save(&self, session: &mut Session) -> Result<()> {
+        let path = self.path(&session.name)?;
+        fs::create_dir_all(&self.dir)?;
+        session.updated_at = now().max(session.updated_at);
+
+        // Write then rename so a crash never leaves half a conversation
+        let tmp = path.with_extension("json.tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(session)?)?;
+        fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            anyhow::bail!("Session '{}' not found. See: yuy sessions list", name);
+        }
+        fs::remove_file(path)?;
+        Ok(())
+    }
+
+    /// All sessions, most recently updated first. Unreadable files are skipped.
+    pub fn list(&self) -> Result<Vec<Session>> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(Vec::new());
+        };
+        let mut sessions: Vec<Session> = entries
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|e| serde_json::from_str(&fs::read_to_string(e.path()).ok()?).ok())
+            .collect();
+        sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at).then_with(|| b.name.cmp(&a.name)));
+        Ok(sessions)
+    }
+
+    /// The most recently updated session with `model`.
+    pub fn latest_for(&self, model: &str) -> Result<Option<Session>> {
+        Ok(self.list()?.into_iter().find(|s| s.model.eq_ignore_ascii_case(model)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saves_lists_and_deletes_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = SessionStore::at(dir.path().join("sessions"));
+        assert!(store.latest_for("Yuuki-NxG-3B").unwrap().is_none());
+
+        let mut first = Session::new("first", "Yuuki-NxG-3B");
+        first.messages.push(Message::new(Role::User, "hello"));
+        store.save(&mut first).unwrap();
+
+        let mut second = Session::new("second", "Yuuki-NxG-vl");
+        second.updated_at = first.updated_at + 10;
+        store.save(&mut second).unwrap();
+
+        assert_eq!(store.load("first").unwrap(), first);
+        assert_eq!(store.latest_for("yuuki-nxg-vl").unwrap().unwrap().name, "second");
+        assert_eq!(store.latest_for("Yuuki-NxG-3B").unwrap().unwrap().name, "first");
+        assert_eq!(store.list().unwrap().len(), 2);
+
+        store.delete("second").unwrap();
+        assert!(store.latest_for("Yuuki-NxG-vl").unwrap().is_none());
+        assert!(store.delete("second").is_err());
+    }
+
+    #[test]
+    fn rejects_unsafe_names() {
+        let store = SessionStore::at("/nonexistent");
+        assert!(store.load("../config").is_err());
+        assert!(store.load(".hidden").is_err());
+        assert!(store.exists("a/b").is_err());
+        assert!(store.exists("chat-2_v1.0").is_ok());
+    }
+
+    #[test]
+    fn exports_markdown_with_system_prompt() {
+        let mut session = Session::new("notes", "Yuuki-NxG-3B");
+        session.created_at = 0;
+        session.system_prompt = Some("Be brief.".to_string());
+        session.messages.push(Message::new(Role::User, "hi"));
+        session.messages.push(Message::new(Role::Assistant, "Hello!\n"));
+
+        let md = session.to_markdown();
+        assert!(md.starts_with("# notes\n\n- Model: Yuuki-NxG-3B\n"));
+        assert!(md.contains("- Created: 1970-01-01 00:00\n"));
+        assert!(md.ends_with("## System\n\nBe brief.\n\n## User\n\nhi\n\n## Assistant\n\nHello!\n"));
+    }
+}
@@ -0,0 +1,244 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::collections::HashMap;
+use crate::config::{load_config, Config};
+
+/// Environment variables that stand in for the model argument, `--quant`
+/// and `--runtime` when they aren't given.
+pub const MODEL_ENV: &str = "YUY_MODEL";
+pub const QUANT_ENV: &str = "YUY_QUANT";
+/// A runtime, or several separated by commas to try in order
+pub const RUNTIME_ENV: &str = "YUY_RUNTIME";
+
+/// Every runtime `--runtime` accepts.
+pub const RUNTIMES: &[&str] = &["llama-cpp", "llama-hf", "ollama"];
+
+/// Runtimes tried in order when nothing names any.
+pub const DEFAULT_RUNTIMES: &[&str] = &["llama-cpp", "ollama"];
+
+/// Model used when nothing names one.
+pub const DEFAULT_MODEL: &str = "Yuuki-NxG-3B";
+
+/// Quantization used when nothing names one and there is nothing to pick
+/// from: no variant sizes to fit, no downloaded file.
+pub const DEFAULT_QUANT: &str = "q4_k_m";
+
+/// Where an effective setting came from, for `--explain`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+    /// A command-line flag, or the `:quant` suffix of the model name
+    Flag(&'static str),
+    /// The session being resumed
+    Session(String),
+    Env(&'static str),
+    /// A key in `config.toml`
+    Config(String),
+    Preset(String),
+    /// Detected; says why
+    Auto(String),
+    Default,
+}
+
+impl std::fmt::Display for Source {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Source::Flag(flag) => write!(f, "{}", flag),
+            Source::Session(name) => write!(f, "session {}", name),
+            Source::Env(var) => write!(f, "${}", var),
+            Source::Config(key) => write!(f, "config: {}", key),
+            Source::Preset(name) => write!(f, "preset {}", name),
+            Source::Auto(reason) => write!(f, "auto: {}", reason),
+            Source::Default => write!(f, "built-in default"),
+        }
+    }
+}
+
+/// An effective value and where it came from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: Source,
+}
+
+impl<T> Setting<T> {
+    pub fn new(value: T, source: Source) -> Self {
+        Self { value, source }
+    }
+}
+
+/// Resolves the settings `download`, `run` and `batch` share, highest
+/// priority first: flag, environment, config file, auto-detection.
+pub struct Settings {
+    config: Config,
+    env: HashMap<&'static str, String>,
+}
+
+impl Settings {
+    pub fn load() -> Result<Self> {
+        let env = [MODEL_ENV, QUANT_ENV, RUNTIME_ENV]
+            .into_iter()
+            .filter_map(|var| Some((var, std::env::var(var).ok()?)))
+            .collect();
+        Ok(Self::new(load_config()?, env))
+    }
+
+    pub fn new(config: Config, env: HashMap<&'static str, String>) -> Self {
+        let env = env.into_iter().filter(|(_, v)| !v.trim().is_empty()).collect();
+        Self { config, env }
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// A config key as a source, naming the profile that set it if any.
+    pub fn config_source(&self, key: &str) -> Source {
+        Source::Config(self.config.origin(key))
+    }
+
+    /// The profile applied to the config, for `--explain`.
+    pub fn profile(&self) -> Option<(&'static str, Setting<String>)> {
+        Some(("profile", self.config.profile.clone()?))
+    }
+
+    fn env(&self, var: &'static str) -> Option<Setting<String>> {
+        let value = self.env.get(var)?.trim().to_string();
+        Some(Setting::new(value, Source::Env(var)))
+    }
+
+    /// The model to use, `DEFAULT_MODEL` when nothing names one.
+    pub fn model(&self, flag: Option<String>) -> Setting<String> {
+        let config = || {
+            let model = self.config.default_model.clone()?;
+            Some(Setting::new(model, self.config_source("default_model")))
+        };
+        flag.map(|m| Setting::new(m, Source::Flag("argument")))
+            .or_else(|| self.env(MODEL_ENV))
+            .or_else(config)
+            .unwrap_or_else(|| Setting::new(DEFAULT_MODEL.to_string(), Source::Default))
+    }
+
+    /// The quantization named by `explicit` (flag, suffix or session),
+    /// the environment or the config. `None` leaves it to auto-detection.
+    pub fn quant(&self, explicit: Option<Setting<String>>) -> Option<Setting<String>> {
+        let config = || {
+            let quant = self.config.default_quant.clone()?;
+            Some(Setting::new(quant, self.config_source("default_quant")))
+        };
+        explicit.or_else(|| self.env(QUANT_ENV)).or_else(config)
+    }
+
+    /// Runtimes to try, in order. `--runtime` names the only one; a
+    /// resumed session's runtime comes before the rest. Otherwise
+    /// `YUY_RUNTIME`, then the config's `runtimes` list (or
+    /// `default_runtime` followed by `DEFAULT_RUNTIMES`).
+    pub fn runtimes(&self, flag: Option<String>, session: Option<(&str, &str)>) -> Result<Setting<Vec<String>>> {
+        let preferred = if let Some(env) = self.env(RUNTIME_ENV) {
+            let list = env.value.split(',').map(|r| r.trim().to_string()).filter(|r| !r.is_empty());
+            Setting::new(list.collect(), env.source)
+        } else if let Some(list) = &self.config.runtimes {
+            Setting::new(list.clone(), self.config_source("runtimes"))
+        } else {
+            let defaults = DEFAULT_RUNTIMES.iter().map(|r| r.to_string());
+            match &self.config.default_runtime {
+                Some(first) => Setting::new(
+                    std::iter::once(first.clone()).chain(defaults).collect(),
+                    self.config_source("default_runtime"),
+                ),
+                None => Setting::new(defaults.collect(), Source::Default),
+            }
+        };
+
+        let chain = match (flag, session) {
+            (Some(runtime), _) => Setting::new(vec![runtime], Source::Flag("--runtime")),
+            (None, Some((name, runtime))) => Setting::new(
+                std::iter::once(runtime.to_string()).chain(preferred.value).collect(),
+                Source::Session(name.to_string()),
+            ),
+            (None, None) => preferred,
+        };
+        let mut runtimes: Vec<String> = Vec::new();
+        for runtime in chain.value {
+            if !runtimes.contains(&runtime) {
+                runtimes.push(runtime);
+            }
+        }
+
+        if runtimes.is_empty() {
+            anyhow::bail!("No runtime to try ({} is empty). Use: {}", chain.source, RUNTIMES.join(", "));
+        }
+        if let Some(unknown) = runtimes.iter().find(|r| !RUNTIMES.contains(&r.as_str())) {
+            anyhow::bail!(
+                "Unknown runtime: {} ({}). Use 'llama-cpp', 'llama-hf', or 'ollama'",
+                unknown,
+                chain.source
+            );
+        }
+        Ok(Setting::new(runtimes, chain.source))
+    }
+}
+
+/// Prints `--explain` output: each setting, its value and its source.
+pub fn print_explain(rows: &[(&str, Setting<String>)]) {
+    eprintln!("{} Effective settings:", "ℹ".bright_blue());
+    let name_width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+    let value_width = rows.iter().map(|(_, s)| s.value.chars().count()).max().unwrap_or(0);
+    for (name, setting) in rows {
+        eprintln!(
+            "  {}  {}  {}",
+            format!("{:<w$}", name, w = name_width).bright_cyan(),
+            format!("{:<w$}", setting.value, w = value_width).bright_white(),
+            format!("({})", setting.source).bright_black()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(config: Config, env: &[(&'static str, &str)]) -> Settings {
+        Settings::new(config, env.iter().map(|(k, v)| (*k, v.to_string())).collect())
+    }
+
+    #[test]
+    fn resolves_flag_then_env_then_config() {
+        let config = Config {
+            default_quant: Some("q8_0".into()),
+            default_model: Some("Yuuki-NxG-vl".into()),
+            ..Config::default()
+        };
+        let s = settings(config.clone(), &[(MODEL_ENV, "Yuuki-best"), (QUANT_ENV, "q4_0")]);
+        assert_eq!(s.model(Some("Yuuki-3.7".into())).source, Source::Flag("argument"));
+        assert_eq!(s.model(None), Setting::new("Yuuki-best".into(), Source::Env(MODEL_ENV)));
+        assert_eq!(s.quant(None).unwrap().value, "q4_0");
+
+        let s = settings(config, &[(QUANT_ENV, " ")]);
+        assert_eq!(s.model(None).source, Source::Config("default_model".into()));
+        assert_eq!(s.quant(None).unwrap().source, Source::Config("default_quant".into()));
+
+        // Nothing set anywhere: built-in model, quant left to auto-detection
+        let s = settings(Config::default(), &[]);
+        assert_eq!(s.model(None), Setting::new(DEFAULT_MODEL.into(), Source::Default));
+        assert!(s.quant(None).is_none());
+    }
+
+    #[test]
+    fn orders_runtimes_to_try() {
+        let s = settings(Config::default(), &[]);
+        assert_eq!(s.runtimes(None, None).unwrap(), Setting::new(vec!["llama-cpp".into(), "ollama".into()], Source::Default));
+        let resumed = s.runtimes(None, Some(("notes", "ollama"))).unwrap();
+        assert_eq!(resumed, Setting::new(vec!["ollama".into(), "llama-cpp".into()], Source::Session("notes".into())));
+        assert_eq!(s.runtimes(Some("llama-hf".into()), Some(("notes", "ollama"))).unwrap().value, ["llama-hf"]);
+
+        let config = Config { runtimes: Some(vec!["ollama".into(), "llama-hf".into()]), ..Config::default() };
+        assert_eq!(settings(config.clone(), &[]).runtimes(None, None).unwrap().value, ["ollama", "llama-hf"]);
+        let env = settings(config, &[(RUNTIME_ENV, "llama-hf, llama-cpp")]).runtimes(None, None).unwrap();
+        assert_eq!(env, Setting::new(vec!["llama-hf".into(), "llama-cpp".into()], Source::Env(RUNTIME_ENV)));
+
+        let config = Config { runtimes: Some(vec!["llama-cpp".into(), "vllm".into()]), ..Config::default() };
+        let err = settings(config, &[]).runtimes(None, None).unwrap_err().to_string();
+        assert!(err.contains("vllm") && err.contains("config: runtimes"), "{}", err);
+        assert!(s.runtimes(Some("vllm".into()), None).is_err());
+    }
+}
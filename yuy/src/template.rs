@@ -0,0 +1,281 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::backend::ChatFormat;
+use colored::Colorize;
+use crate::config::get_config_dir;
+use crate::utils::valid_name;
+
+/// Templates shipped with the binary.
+const BUILTIN_TEMPLATES: &str = include_str!("templates.toml");
+
+/// A named system prompt with variables and the chat format to render it in.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Template {
+    /// Taken from the file name for user templates
+    #[serde(default, skip_serializing)]
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub format: ChatFormat,
+    pub system: String,
+    /// Default values; an empty string marks a variable as required
+    #[serde(default)]
+    pub variables: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TemplateFile {
+    #[serde(default)]
+    templates: Vec<Template>,
+}
+
+impl Template {
+    /// Skeleton for `yuy template new`.
+    pub fn skeleton(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            description: "Describe what this template is for".to_string(),
+            format: ChatFormat::default(),
+            system: "You are Yuuki, a helpful assistant. Answer in {{language}}.".to_string(),
+            variables: BTreeMap::from([("language".to_string(), "English".to_string())]),
+        }
+    }
+
+    /// Variables referenced as `{{name}}` in the system prompt.
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names = Vec::new();
+        let mut rest = self.system.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            let name = rest[start + 2..start + 2 + len].trim().to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+            rest = &rest[start + 2 + len + 2..];
+        }
+        names
+    }
+
+    /// System prompt with variables filled in from `values`, then defaults.
+    pub fn render(&self, values: &BTreeMap<String, String>) -> Result<String> {
+        let placeholders = self.placeholders();
+        for key in values.keys() {
+            if !placeholders.contains(key) && !self.variables.contains_key(key) {
+                anyhow::bail!(
+                    "Template '{}' has no variable '{}' (variables: {})",
+                    self.name,
+                    key,
+                    if placeholders.is_empty() { "none".to_string() } else { placeholders.join(", ") }
+                );
+            }
+        }
+
+        let mut system = self.system.clone();
+        for name in &placeholders {
+            let value = values
+                .get(name)
+                .or_else(|| self.variables.get(name))
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Template '{}' needs a value for '{}'. Pass it with: --var {}=<value>",
+                        self.name,
+                        name,
+                        name
+                    )
+                })?;
+            system = replace_placeholder(&system, name, value);
+        }
+        Ok(system)
+    }
+}
+
+/// Replaces `{{name}}`, allowing spaces inside the braces.
+fn replace_placeholder(text: &str, name: &str, value: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        if rest[start + 2..start + 2 + len].trim() == name {
+            out.push_str(value);
+        } else {
+            out.push_str(&rest[start..start + 2 + len + 2]);
+        }
+        rest = &rest[start + 2 + len + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Parses a `--var key=value` argument.
+pub fn parse_var(arg: &str) -> Result<(String, String)> {
+    match arg.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_string(), value.to_string())),
+        _ => anyhow::bail!("Invalid variable '{}'. Expected key=value.", arg),
+    }
+}
+
+/// Built-in templates plus `~/.yuuki/templates/*.toml`, which replace
+/// built-ins with the same name.
+pub struct TemplateStore {
+    dir: PathBuf,
+}
+
+impl TemplateStore {
+    pub fn open() -> Result<Self> {
+        Ok(Self::at(get_config_dir()?.join("templates")))
+    }
+
+    pub fn at(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn builtin() -> Vec<Template> {
+        let file: TemplateFile = toml::from_str(BUILTIN_TEMPLATES).expect("built-in templates are valid");
+        file.templates
+    }
+
+    /// Path of the user file for `name`, which need not exist.
+    pub fn path(&self, name: &str) -> Result<PathBuf> {
+        if !valid_name(name) {
+            anyhow::bail!("Invalid template name '{}'. Use letters, digits, '-', '_' and '.'.", name);
+        }
+        Ok(self.dir.join(format!("{}.toml", name)))
+    }
+
+    /// All templates, sorted by name, with whether each comes from a user file.
+    /// Unreadable user files are skipped with a warning.
+    pub fn list(&self) -> Result<Vec<(Template, bool)>> {
+        let mut templates: Vec<(Template, bool)> = Self::builtin().into_iter().map(|t| (t, false)).collect();
+
+        if let Ok(entries) = fs::read_dir(&self.dir) {
+            let mut paths: Vec<PathBuf> = entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+                .collect();
+            paths.sort();
+            for path in paths {
+                // One broken file shouldn't hide every other template
+                let template = match load_file(&path) {
+                    Ok(template) => template,
+                    Err(e) => {
+                        eprintln!("{} Skipping {}: {:#}", "⚠".bright_yellow(), path.display(), e);
+                        continue;
+                    }
+                };
+                templates.retain(|(t, _)| !t.name.eq_ignore_ascii_case(&template.name));
+                templates.push((template, true));
+            }
+        }
+
+        templates.sort_by_key(|(t, _)| t.name.to_lowercase());
+        Ok(templates)
+    }
+
+    pub fn find(&self, name: &str) -> Result<Template> {
+        let templates = self.list()?;
+        if let Some((template, _)) = templates.iter().find(|(t, _)| t.name.eq_ignore_ascii_case(name)) {
+            return Ok(template.clone());
+        }
+        let names: Vec<_> = templates.iter().map(|(t, _)| t.name.as_str()).collect();
+        anyhow::bail!("Template '{}' not found. Available: {}", name, names.join(", "))
+    }
+
+    /// Writes `template` to its user file.
+    pub fn save(&self, template: &Template) -> Result<PathBuf> {
+        let path = self.path(&template.name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, toml::to_string_pretty(template)?)?;
+        Ok(path)
+    }
+}
+
+/// Loads a user template file, naming it after the file.
+pub fn load_file(path: &Path) -> Result<Template> {
+    let content = fs::read_to_string(path)?;
+    let mut template: Template =
+        toml::from_str(&content).with_context(|| format!("Invalid template file {}", path.display()))?;
+    template.name = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(template)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_render_with_defaults_and_overrides() {
+        let store = TemplateStore::at("/nonexistent");
+        let translation = store.find("Translation").unwrap();
+        assert!(translation.render(&BTreeMap::new()).unwrap().contains("into English."));
+
+        let values = BTreeMap::from([("language".to_string(), "Japanese".to_string())]);
+        assert!(translation.render(&values).unwrap().contains("into Japanese."));
+
+        let wrong = BTreeMap::from([("lang".to_string(), "Japanese".to_string())]);
+        assert!(translation.render(&wrong).is_err());
+
+        let bilingual = store.find("bilingual").unwrap();
+        assert_eq!(bilingual.placeholders(), ["primary", "secondary"]);
+        assert!(store.find("missing").is_err());
+    }
+
+    #[test]
+    fn required_variables_must_be_given() {
+        let mut template = Template::skeleton("review");
+        template.system = "Review this {{ kind }} for {{team}}.".to_string();
+        template.variables = BTreeMap::from([("team".to_string(), String::new())]);
+
+        assert!(template.render(&BTreeMap::new()).is_err());
+        let values = BTreeMap::from([
+            ("kind".to_string(), "diff".to_string()),
+            ("team".to_string(), "infra".to_string()),
+        ]);
+        assert_eq!(template.render(&values).unwrap(), "Review this diff for infra.");
+    }
+
+    #[test]
+    fn user_templates_override_builtins() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = TemplateStore::at(dir.path());
+
+        let mut coding = Template::skeleton("coding");
+        coding.format = ChatFormat::Llama3;
+        coding.system = "Write Rust.".to_string();
+        coding.variables.clear();
+        store.save(&coding).unwrap();
+        store.save(&Template::skeleton("haiku")).unwrap();
+
+        let templates = store.list().unwrap();
+        assert_eq!(templates.len(), 4);
+        let (found, user) = templates.iter().find(|(t, _)| t.name == "coding").unwrap();
+        assert!(*user);
+        assert_eq!(found.format, ChatFormat::Llama3);
+        assert_eq!(found.render(&BTreeMap::new()).unwrap(), "Write Rust.");
+        assert!(store.path("../x").is_err());
+
+        fs::write(dir.path().join("broken.toml"), "system = [").unwrap();
+        assert_eq!(store.list().unwrap().len(), 4);
+        assert_eq!(store.find("haiku").unwrap().name, "haiku");
+    }
+
+    #[test]
+    fn parses_vars() {
+        assert_eq!(parse_var("language=Spanish").unwrap(), ("language".into(), "Spanish".into()));
+        assert_eq!(parse_var("q=a=b").unwrap(), ("q".into(), "a=b".into()));
+        assert!(parse_var("novalue").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! Stand-ins shared by the tests: a local HTTP server and fake runtime
+//! binaries.
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request received by `serve`.
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// Header lines as sent
+    pub head: String,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Value of header `name`, matched case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.head.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+}
+
+/// What `serve` answers with. `Content-Length` is always the full body's.
+pub struct Response {
+    status: &'static str,
+    headers: Vec<String>,
+    body: Vec<u8>,
+    /// Close the connection after this many body bytes
+    cut_after: Option<usize>,
+}
+
+impl Response {
+    pub fn new(status: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self { status, headers: Vec::new(), body: body.into(), cut_after: None }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new("200 OK", body)
+    }
+
+    pub fn header(mut self, name: &str, value: impl std::fmt::Display) -> Self {
+        self.headers.push(format!("{}: {}", name, value));
+        self
+    }
+
+    /// Advertises the whole body but drops the connection after `n` bytes.
+    pub fn cut_after(mut self, n: usize) -> Self {
+        self.cut_after = Some(n);
+        self
+    }
+}
+
+/// Serves `respond` on a local port until the test ends, returning the
+/// base URL, e.g. `http://127.0.0.1:40123`. HEAD requests get the headers
+/// only.
+pub async fn serve(respond: impl Fn(&Request) -> Response + Send + Sync + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let respond = Arc::new(respond);
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let respond = Arc::clone(&respond);
+            tokio::spawn(async move {
+                let request = read_request(&mut socket).await;
+                let response = respond(&request);
+                let mut head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n", response.status, response.body.len());
+                for header in &response.headers {
+                    head.push_str(header);
+                    head.push_str("\r\n");
+                }
+                head.push_str("\r\n");
+                let end = match response.cut_after {
+                    _ if request.method == "HEAD" => 0,
+                    Some(n) => n.min(response.body.len()),
+                    None => response.body.len(),
+                };
+                let _ = socket.write_all(head.as_bytes()).await;
+                let _ = socket.write_all(&response.body[..end]).await;
+                socket.shutdown().await.ok();
+            });
+        }
+    });
+
+    format!("http://{}", addr)
+}
+
+/// Reads the headers and the body, so uploads finish before the reply.
+async fn read_request(socket: &mut TcpStream) -> Request {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 65536];
+    let head_end = loop {
+        let n = socket.read(&mut buf).await.unwrap_or(0);
+        data.extend_from_slice(&buf[..n]);
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end;
+        }
+        if n == 0 {
+            break data.len();
+        }
+    };
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut request = Request { method: String::new(), path: String::new(), head, body: Vec::new() };
+    let mut words = request.head.split_whitespace();
+    request.method = words.next().unwrap_or_default().to_string();
+    request.path = words.next().unwrap_or_default().to_string();
+
+    let length: usize = request.header("content-length").and_then(|v| v.parse().ok()).unwrap_or(0);
+    let body_start = (head_end + 4).min(data.len());
+    while data.len() < body_start + length {
+        let n = socket.read(&mut buf).await.unwrap_or(0);
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+    request.body = data[body_start..].to_vec();
+    request
+}
+
+/// Writes an executable `sh` script called `name` into `dir`, e.g. a
+/// stand-in for `llama-cli`.
+#[cfg(unix)]
+pub fn fake_binary(dir: &Path, name: &str, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join(name);
+    std::fs::write(&path, format!("#!/bin/sh\n{}", script)).unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
@@ -1,4 +1,4 @@
-use anyhow::Result;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[derive(Debug, Clone, PartialEq)]
@@ -28,10 +28,163 @@ pub fn detect_platform() -> Platform {
     }
 }
 
-pub fn get_available_ram_gb() -> usize {
-    // Simplified: assume 8GB if we can't detect
-    // In production, use sys-info or similar
-    8
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// Where [`SystemInfo`] reads from. Tests point these at fixture files.
+#[derive(Debug, Clone)]
+pub struct SystemPaths {
+    pub meminfo: PathBuf,
+    pub cpuinfo: PathBuf,
+    /// cgroup mount point; v2 files live at its root, v1 under `memory/`
+    pub cgroup: PathBuf,
+}
+
+impl Default for SystemPaths {
+    fn default() -> Self {
+        Self {
+            meminfo: PathBuf::from("/proc/meminfo"),
+            cpuinfo: PathBuf::from("/proc/cpuinfo"),
+            cgroup: PathBuf::from("/sys/fs/cgroup"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CpuFeatures {
+    pub avx2: bool,
+    pub avx512: bool,
+    pub neon: bool,
+}
+
+/// Memory and CPU of the machine, as far as Yuy can tell.
+///
+/// Memory is read from `/proc/meminfo` (Linux, Termux) and capped by the
+/// cgroup v1/v2 memory limit when running inside a container. Values are
+/// `None` where they can't be detected.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SystemInfo {
+    pub total_ram: Option<u64>,
+    pub available_ram: Option<u64>,
+    pub cpu_cores: usize,
+    pub cpu_features: CpuFeatures,
+}
+
+impl SystemInfo {
+    pub fn detect() -> Self {
+        Self::from_paths(&SystemPaths::default())
+    }
+
+    pub fn from_paths(paths: &SystemPaths) -> Self {
+        let meminfo = std::fs::read_to_string(&paths.meminfo).unwrap_or_default();
+        let mut total_ram = meminfo_kb(&meminfo, "MemTotal").map(|kb| kb * 1024);
+        let mut available_ram = meminfo_kb(&meminfo, "MemAvailable")
+            .or_else(|| meminfo_kb(&meminfo, "MemFree"))
+            .map(|kb| kb * 1024);
+
+        if let Some((limit, usage)) = cgroup_memory(&paths.cgroup) {
+            total_ram = Some(total_ram.map_or(limit, |t| t.min(limit)));
+            let headroom = limit.saturating_sub(usage);
+            available_ram = Some(available_ram.map_or(headroom, |a| a.min(headroom)));
+        }
+
+        let cpuinfo = std::fs::read_to_string(&paths.cpuinfo).unwrap_or_default();
+        let processors = cpuinfo
+            .lines()
+            .filter(|l| l.split(':').next().is_some_and(|k| k.trim() == "processor"))
+            .count();
+        let cpu_cores = if processors > 0 {
+            processors
+        } else {
+            std::thread::available_parallelism().map_or(1, |n| n.get())
+        };
+
+        let cpu_features = if cpuinfo.is_empty() {
+            native_cpu_features()
+        } else {
+            cpuinfo_features(&cpuinfo)
+        };
+
+        Self {
+            total_ram,
+            available_ram,
+            cpu_cores,
+            cpu_features,
+        }
+    }
+}
+
+fn meminfo_kb(meminfo: &str, key: &str) -> Option<u64> {
+    meminfo.lines().find_map(|line| {
+        let (k, v) = line.split_once(':')?;
+        if k.trim() != key {
+            return None;
+        }
+        v.split_whitespace().next()?.parse().ok()
+    })
+}
+
+/// `(limit, usage)` in bytes if a cgroup memory limit is set.
+fn cgroup_memory(root: &Path) -> Option<(u64, u64)> {
+    let read = |path: PathBuf| -> Option<u64> {
+        std::fs::read_to_string(path).ok()?.trim().parse().ok()
+    };
+
+    // v2: "max" means unlimited and doesn't parse
+    let (limit, usage) = match read(root.join("memory.max")) {
+        Some(limit) => (limit, read(root.join("memory.current"))),
+        None => (
+            read(root.join("memory/memory.limit_in_bytes"))?,
+            read(root.join("memory/memory.usage_in_bytes")),
+        ),
+    };
+
+    // v1 reports "unlimited" as a huge page-aligned number
+    if limit >= u64::MAX / 2 {
+        return None;
+    }
+    Some((limit, usage.unwrap_or(0)))
+}
+
+fn cpuinfo_features(cpuinfo: &str) -> CpuFeatures {
+    let flags: Vec<&str> = cpuinfo
+        .lines()
+        .filter_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            matches!(k.trim(), "flags" | "Features").then_some(v)
+        })
+        .next()
+        .map(|v| v.split_whitespace().collect())
+        .unwrap_or_default();
+
+    CpuFeatures {
+        avx2: flags.contains(&"avx2"),
+        avx512: flags.contains(&"avx512f"),
+        // aarch64 reports NEON as asimd
+        neon: flags.contains(&"neon") || flags.contains(&"asimd"),
+    }
+}
+
+/// Features of the CPU we're running on, for platforms without /proc/cpuinfo.
+fn native_cpu_features() -> CpuFeatures {
+    #[cfg(target_arch = "x86_64")]
+    {
+        CpuFeatures {
+            avx2: std::is_x86_feature_detected!("avx2"),
+            avx512: std::is_x86_feature_detected!("avx512f"),
+            neon: false,
+        }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        CpuFeatures {
+            neon: true,
+            ..CpuFeatures::default()
+        }
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        CpuFeatures::default()
+    }
 }
 
 pub fn command_exists(cmd: &str) -> bool {
@@ -42,6 +195,44 @@ pub fn command_exists(cmd: &str) -> bool {
         .unwrap_or(false)
 }
 
+/// Opens `path` in `$VISUAL` / `$EDITOR` and waits for it to exit.
+pub fn open_in_editor(path: &Path) -> anyhow::Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+    // Allow editors with arguments, e.g. `code --wait`
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("Failed to start editor '{}': {}. Set $EDITOR.", editor, e))?;
+    if !status.success() {
+        anyhow::bail!("Editor exited with {}", status);
+    }
+    Ok(())
+}
+
+/// Whether `name` is safe to use as a file name: letters, digits, '-', '_'
+/// and '.', not starting with '.'.
+pub fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// The candidate closest to `input`, when one is close enough to suggest.
+pub fn did_you_mean<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+    let input = input.to_lowercase();
+    candidates
+        .into_iter()
+        .map(|c| (strsim::jaro_winkler(&input, &c.to_lowercase()), c))
+        .filter(|(score, _)| *score > 0.8)
+        .max_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, c)| c)
+}
+
 pub fn format_size(bytes: u64) -> String {
     const GB: u64 = 1024 * 1024 * 1024;
     const MB: u64 = 1024 * 1024;
@@ -58,11 +249,422 @@ pub fn format_size(bytes: u64) -> String {
     }
 }
 
-pub fn recommend_quantization(platform: Platform, ram_gb: usize) -> &'static str {
-    match (platform, ram_gb) {
-        (Platform::Termux, _) => "q4_0",
-        (_, ram) if ram < 8 => "q4_k_m",
-        (_, ram) if ram < 16 => "q5_k_m",
-        _ => "q8_0",
+/// UTC `(year, month, day, hour, minute, second)` for a Unix timestamp.
+pub fn utc_datetime(secs: u64) -> (i64, u32, u32, u32, u32, u32) {
+    let days = (secs / 86_400) as i64;
+    let rem = secs % 86_400;
+
+    // Howard Hinnant's civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day, (rem / 3600) as u32, (rem % 3600 / 60) as u32, (rem % 60) as u32)
+}
+
+/// `2026-10-18 14:03` (UTC).
+pub fn format_timestamp(secs: u64) -> String {
+    let (y, mo, d, h, mi, _) = utc_datetime(secs);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", y, mo, d, h, mi)
+}
+
+/// Total size of the files directly in `dir`, e.g. a model's directory.
+pub fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .map(|files| files.flatten().filter_map(|f| f.metadata().ok()).map(|m| m.len()).sum())
+        .unwrap_or(0)
+}
+
+/// Whether `a` and `b` are on the same filesystem, so moving between them
+/// is a rename. Paths that don't exist yet count as their nearest existing
+/// parent; false when that can't be told.
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let device = |path: &Path| {
+            let existing = path.ancestors().find(|p| p.exists())?;
+            std::fs::metadata(existing).ok().map(|m| m.dev())
+        };
+        matches!((device(a), device(b)), (Some(a), Some(b)) if a == b)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        false
+    }
+}
+
+/// Whether a rename failed because it would cross filesystems. Checked
+/// by OS error code; `ErrorKind::CrossesDevices` needs Rust 1.85.
+fn crosses_devices(e: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    return e.raw_os_error() == Some(libc::EXDEV);
+    // ERROR_NOT_SAME_DEVICE
+    #[cfg(windows)]
+    return e.raw_os_error() == Some(17);
+    #[cfg(not(any(unix, windows)))]
+    return false;
+}
+
+/// Renames `from` to `to`, copying instead when they are on different
+/// filesystems, e.g. a separate models volume.
+pub fn move_path(from: &Path, to: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    match std::fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if crosses_devices(&e) => {
+            if let Err(e) = copy_all(from, to) {
+                // Don't leave half a copy behind, e.g. when the disk fills up
+                let _ = if to.is_dir() { std::fs::remove_dir_all(to) } else { std::fs::remove_file(to) };
+                return Err(e.into());
+            }
+            if from.is_dir() {
+                std::fs::remove_dir_all(from)?;
+            } else {
+                std::fs::remove_file(from)?;
+            }
+            Ok(())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn copy_all(from: &Path, to: &Path) -> std::io::Result<()> {
+    if !from.is_dir() {
+        return std::fs::copy(from, to).map(|_| ());
+    }
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        copy_all(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
+}
+
+/// Context length models are run with unless told otherwise.
+pub const DEFAULT_CONTEXT: u32 = 4096;
+
+/// Compute buffers and runtime bookkeeping on top of weights and KV cache.
+const RUNTIME_OVERHEAD: u64 = 512 * 1024 * 1024;
+
+/// Free space on the filesystem holding `path`, via `df`. A path that
+/// doesn't exist yet is measured at its nearest existing parent.
+pub fn free_disk_space(path: &Path) -> Option<u64> {
+    let path = path.ancestors().find(|p| p.exists())?;
+    let output = Command::new("df").arg("-Pk").arg(path).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let kb: u64 = stdout.lines().nth(1)?.split_whitespace().nth(3)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+/// Parses a parameter count such as `7B`, `1.5B` or `81M`.
+pub fn parse_parameters(parameters: &str) -> Option<f64> {
+    let s = parameters.trim();
+    let (number, scale) = match s.chars().last()?.to_ascii_uppercase() {
+        'B' => (&s[..s.len() - 1], 1e9),
+        'M' => (&s[..s.len() - 1], 1e6),
+        'K' => (&s[..s.len() - 1], 1e3),
+        _ => (s, 1.0),
+    };
+    let value: f64 = number.trim().parse().ok()?;
+    (value > 0.0).then_some(value * scale)
+}
+
+/// Approximate bits per weight of a GGUF quantization.
+fn bits_per_weight(quant: &str) -> f64 {
+    match quant {
+        "f32" => 32.0,
+        "f16" | "bf16" => 16.0,
+        "q8_0" => 8.5,
+        "q6_k" => 6.6,
+        q if q.starts_with("q5") => 5.7,
+        q if q.starts_with("q4") || q.starts_with("iq4") => 4.8,
+        q if q.starts_with("q3") || q.starts_with("iq3") => 3.9,
+        q if q.starts_with("q2") || q.starts_with("iq2") => 2.9,
+        _ => 8.0,
+    }
+}
+
+/// Rough f16 KV-cache size for `context` tokens.
+///
+/// Without the model's metadata, width and layer count are derived from the
+/// parameter count assuming llama-like proportions (width ≈ 128 × layers,
+/// parameters ≈ 12 × layers × width²) and no grouped-query attention, so
+/// this errs on the generous side.
+pub fn estimate_kv_cache(parameters: f64, context: u32) -> u64 {
+    let width = (parameters * 128.0 / 12.0).cbrt();
+    let layers = (width / 128.0).max(1.0);
+    (2.0 * layers * width * 2.0 * context as f64) as u64
+}
+
+/// A quantization variant and its file size in bytes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Variant {
+    pub quant: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantRecommendation {
+    pub quant: String,
+    /// Whether it fits in memory and on disk; otherwise it's the smallest variant
+    pub fits: bool,
+    /// Human-readable explanation of the choice
+    pub reason: String,
+}
+
+/// Picks the largest (highest-quality) variant whose weights, KV cache for
+/// `context` tokens and runtime overhead fit in memory, and whose file fits
+/// in `free_disk`. Falls back to the smallest variant when none fits.
+pub fn recommend_quant(
+    variants: &[Variant],
+    parameters: Option<&str>,
+    context: u32,
+    system: &SystemInfo,
+    platform: &Platform,
+    free_disk: Option<u64>,
+) -> Option<QuantRecommendation> {
+    let mut variants = variants.to_vec();
+    variants.sort_by_key(|v| std::cmp::Reverse(v.size));
+    let largest = variants.first()?;
+
+    // Without a declared size, estimate it from the largest file
+    let parameters = parameters
+        .and_then(parse_parameters)
+        .unwrap_or_else(|| largest.size as f64 * 8.0 / bits_per_weight(&largest.quant));
+    let kv_cache = estimate_kv_cache(parameters, context);
+    let needed = |v: &Variant| v.size + kv_cache + RUNTIME_OVERHEAD;
+
+    let (memory, memory_label) = match (system.available_ram, system.total_ram) {
+        (Some(available), _) => (available, format!("{} RAM available", format_size(available))),
+        (None, Some(total)) => (total, format!("{} RAM", format_size(total))),
+        (None, None) => (8 * GIB, "8 GB RAM assumed (memory could not be detected)".to_string()),
+    };
+    // Android kills memory-hungry apps well before RAM runs out
+    let budget = if *platform == Platform::Termux { memory / 4 * 3 } else { memory };
+
+    let fits_memory = |v: &Variant| needed(v) <= budget;
+    let fits_disk = |v: &Variant| free_disk.is_none_or(|free| v.size <= free);
+    let breakdown = |v: &Variant| {
+        format!(
+            "{} needs ~{} ({} weights + {} KV cache for {} tokens + overhead)",
+            v.quant,
+            format_size(needed(v)),
+            format_size(v.size),
+            format_size(kv_cache),
+            context
+        )
+    };
+
+    let Some(index) = variants.iter().position(|v| fits_memory(v) && fits_disk(v)) else {
+        let smallest = variants.last()?;
+        let reason = if !fits_disk(smallest) {
+            format!(
+                "even {} ({}) does not fit in {} of free disk space",
+                smallest.quant,
+                format_size(smallest.size),
+                format_size(free_disk.unwrap_or(0))
+            )
+        } else {
+            format!("even the smallest variant: {}, with {}", breakdown(smallest), memory_label)
+        };
+        return Some(QuantRecommendation {
+            quant: smallest.quant.clone(),
+            fits: false,
+            reason,
+        });
+    };
+
+    let chosen = &variants[index];
+    let mut reason = format!("{}, with {}", breakdown(chosen), memory_label);
+    if index > 0 {
+        let better = &variants[index - 1];
+        if !fits_disk(better) {
+            reason.push_str(&format!(
+                "; {} ({}) does not fit in {} of free disk space",
+                better.quant,
+                format_size(better.size),
+                format_size(free_disk.unwrap_or(0))
+            ));
+        } else {
+            reason.push_str(&format!("; {} would need ~{}", better.quant, format_size(needed(better))));
+        }
+    } else if variants.len() > 1 {
+        reason.push_str("; this is the highest-quality variant");
+    }
+
+    Some(QuantRecommendation {
+        quant: chosen.quant.clone(),
+        fits: true,
+        reason,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures(cpuinfo: &str, cgroup: &str) -> SystemPaths {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/system");
+        SystemPaths {
+            meminfo: dir.join("meminfo"),
+            cpuinfo: dir.join(cpuinfo),
+            cgroup: dir.join(cgroup),
+        }
+    }
+
+    #[test]
+    fn reads_meminfo_and_x86_features() {
+        let info = SystemInfo::from_paths(&fixtures("cpuinfo-x86", "no-cgroup"));
+
+        assert_eq!(info.total_ram, Some(16303428 * 1024));
+        assert_eq!(info.available_ram, Some(9871344 * 1024));
+        assert_eq!(info.cpu_cores, 4);
+        assert_eq!(
+            info.cpu_features,
+            CpuFeatures { avx2: true, avx512: true, neon: false }
+        );
+    }
+
+    #[test]
+    fn detects_neon_on_arm() {
+        let info = SystemInfo::from_paths(&fixtures("cpuinfo-arm", "no-cgroup"));
+
+        assert_eq!(info.cpu_cores, 8);
+        assert_eq!(
+            info.cpu_features,
+            CpuFeatures { avx2: false, avx512: false, neon: true }
+        );
+    }
+
+    #[test]
+    fn cgroup_v2_limit_caps_memory() {
+        let info = SystemInfo::from_paths(&fixtures("cpuinfo-x86", "cgroup-v2"));
+
+        assert_eq!(info.total_ram, Some(4 * GIB));
+        assert_eq!(info.available_ram, Some(3 * GIB));
+    }
+
+    #[test]
+    fn unlimited_cgroup_v1_is_ignored() {
+        let info = SystemInfo::from_paths(&fixtures("cpuinfo-x86", "cgroup-v1"));
+
+        assert_eq!(info.total_ram, Some(16303428 * 1024));
+    }
+
+    fn variants() -> Vec<Variant> {
+        [("q2_k", 3 * GIB), ("q4_k_m", 4 * GIB + GIB / 2), ("q8_0", 8 * GIB)]
+            .into_iter()
+            .map(|(quant, size)| Variant { quant: quant.to_string(), size })
+            .collect()
+    }
+
+    fn with_ram(available: u64) -> SystemInfo {
+        SystemInfo {
+            total_ram: Some(16 * GIB),
+            available_ram: Some(available),
+            ..SystemInfo::default()
+        }
+    }
+
+    #[test]
+    fn parses_parameter_counts() {
+        assert_eq!(parse_parameters("7B"), Some(7e9));
+        assert_eq!(parse_parameters("1.5b"), Some(1.5e9));
+        assert_eq!(parse_parameters("81M"), Some(81e6));
+        assert_eq!(parse_parameters("big"), None);
+    }
+
+    #[test]
+    fn kv_cache_scales_with_model_and_context() {
+        let small = estimate_kv_cache(81e6, DEFAULT_CONTEXT);
+        let large = estimate_kv_cache(7e9, DEFAULT_CONTEXT);
+        assert!(small < 256 * 1024 * 1024);
+        assert!(large > GIB && large < 4 * GIB);
+        assert_eq!(estimate_kv_cache(7e9, 2 * DEFAULT_CONTEXT) / 2, large);
+    }
+
+    #[test]
+    fn recommends_largest_variant_that_fits_in_memory() {
+        let pick = |ram| {
+            recommend_quant(&variants(), Some("7B"), DEFAULT_CONTEXT, &with_ram(ram), &Platform::Linux, None)
+                .unwrap()
+        };
+
+        let roomy = pick(14 * GIB);
+        assert_eq!(roomy.quant, "q8_0");
+        assert!(roomy.fits);
+
+        let tight = pick(8 * GIB);
+        assert_eq!(tight.quant, "q4_k_m");
+        assert!(tight.reason.contains("q8_0 would need"), "{}", tight.reason);
+
+        let starved = pick(2 * GIB);
+        assert_eq!(starved.quant, "q2_k");
+        assert!(!starved.fits);
+
+        // Termux keeps a safety margin
+        let phone = recommend_quant(&variants(), Some("7B"), DEFAULT_CONTEXT, &with_ram(10 * GIB), &Platform::Termux, None);
+        assert_eq!(phone.unwrap().quant, "q4_k_m");
+    }
+
+    #[test]
+    fn free_disk_space_limits_recommendation() {
+        let pick = recommend_quant(
+            &variants(),
+            Some("7B"),
+            DEFAULT_CONTEXT,
+            &with_ram(14 * GIB),
+            &Platform::Linux,
+            Some(5 * GIB),
+        )
+        .unwrap();
+        assert_eq!(pick.quant, "q4_k_m");
+        assert!(pick.reason.contains("free disk space"), "{}", pick.reason);
+    }
+
+    #[test]
+    fn estimates_parameters_from_file_sizes_when_unknown() {
+        let tiny = [Variant { quant: "f16".to_string(), size: 162_000_000 }];
+        let pick = recommend_quant(&tiny, None, DEFAULT_CONTEXT, &with_ram(GIB), &Platform::Linux, None).unwrap();
+        assert_eq!(pick.quant, "f16");
+        assert!(pick.fits);
+        assert!(recommend_quant(&[], None, DEFAULT_CONTEXT, &with_ram(GIB), &Platform::Linux, None).is_none());
+    }
+
+    #[test]
+    fn formats_utc_timestamps() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(utc_datetime(951_782_400), (2000, 2, 29, 0, 0, 0));
+        assert_eq!(format_timestamp(1_792_332_191), "2026-10-18 14:03");
+    }
+
+    #[test]
+    fn moves_a_model_directory_within_a_filesystem() {
+        let root = tempfile::tempdir().unwrap();
+        let from = root.path().join("a/Yuuki-best");
+        std::fs::create_dir_all(&from).unwrap();
+        std::fs::write(from.join("yuuki-best-q4_0.gguf"), vec![0u8; 1000]).unwrap();
+        std::fs::write(from.join("yuuki-best-q8_0.gguf"), vec![0u8; 24]).unwrap();
+        assert_eq!(dir_size(&from), 1024);
+
+        let to = root.path().join("b/models/Yuuki-best");
+        assert!(same_filesystem(&from, &to));
+        move_path(&from, &to).unwrap();
+        assert!(!from.exists());
+        assert_eq!(dir_size(&to), 1024);
+        assert_eq!(dir_size(&from), 0);
     }
 }